@@ -0,0 +1,549 @@
+use rand::{Rng, RngCore};
+
+use crate::estimator::{qualified_name, Estimate, PiEstimator};
+use crate::map;
+use crate::points::{PointCursor, PointSource};
+
+// If a needle of length l is dropped n times on a surface on which parallel lines...
+// ...are drawn t units appart, and if x of those comes to rest crossing a line...
+// ...then pi ~ 2nl/xt
+//
+// A needle longer than t can cross several lines. Counting every crossing keeps
+// pi ~ 2nl/xt, since a needle crosses 2l / (pi t) lines on average whatever its length.
+
+/// Shortest needle allowed, as a fraction of the line spacing: shorter needles
+/// so rarely cross a line that the estimate would need far too many drops.
+pub const MIN_LENGTH_RATIO: f64 = 1e-3;
+
+/// Longest needle allowed, as a multiple of the line spacing.
+pub const MAX_LENGTH_RATIO: f64 = 1e3;
+
+/// Where a needle came to rest.
+///
+/// `start_x` and `end_x` are measured from the line to the left of the start,
+/// `end_y` is how far the end is above the start.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Needle {
+    pub start_x: f64,
+    pub end_x: f64,
+    pub end_y: f64,
+    pub crosses: bool,
+}
+
+/// How each drop is scored, see [`BuffonsNeedle::with_variance_reduction`].
+///
+/// Every mode scores a drop with a value whose mean is the number of lines
+/// 2l / (pi t) a needle crosses on average, and pi is worked out from their average.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VarianceReduction {
+    /// The number of lines the needle crosses: 1 or 0 for short needles.
+    Crossings,
+    /// Rao-Blackwellised: the exact number of lines l |cos(angle)| / t that a
+    /// needle at this angle crosses on average, whatever its position. For short
+    /// needles it is the probability of crossing a line.
+    RaoBlackwell,
+    /// The angles are drawn more often across the lines, where needles cross,
+    /// and a crossing scores the ratio of the uniform density of its angle to
+    /// the density it was drawn from.
+    ImportanceSampling,
+}
+
+impl VarianceReduction {
+    /// Name used in the output, e.g. `rao-blackwellised`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            VarianceReduction::Crossings => "crossings",
+            VarianceReduction::RaoBlackwell => "rao-blackwellised",
+            VarianceReduction::ImportanceSampling => "importance sampled",
+        }
+    }
+}
+
+/// How the direction of each needle is drawn, see [`BuffonsNeedle::with_direction`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    /// An angle uniform in [0, 2 pi), which uses pi to estimate pi.
+    Angle,
+    /// A point uniform in the unit disk, drawn by rejection from the square
+    /// around it and scaled to length 1. Nothing in the drop depends on pi.
+    ///
+    /// The points are always drawn from the `rng`, whatever the [`PointSource`].
+    Disk,
+}
+
+/// Buffon's needle: drop needles on parallel lines and count how many cross a line.
+///
+/// One sample is one needle drop. The position and angle of each needle are
+/// pseudo-random unless another [`PointSource`] is chosen with
+/// [`BuffonsNeedle::with_points`], and crossings are counted unless a
+/// [`VarianceReduction`] is chosen.
+#[derive(Debug, Clone)]
+pub struct BuffonsNeedle {
+    needle_length: f64,
+    parallel_width: f64,
+    points: PointCursor,
+    reduction: VarianceReduction,
+    direction: Direction,
+    drops: u64,
+    crossings: u64,
+    crossed_needles: u64,
+    // Sums of the score of each drop and of its square
+    score: f64,
+    score_squares: f64,
+}
+
+impl BuffonsNeedle {
+    /// Needles as long as the lines are apart.
+    pub fn new() -> Self {
+        BuffonsNeedle {
+            needle_length: 1_f64,
+            parallel_width: 1_f64,
+            points: PointCursor::default(),
+            reduction: VarianceReduction::Crossings,
+            direction: Direction::Angle,
+            drops: 0,
+            crossings: 0,
+            crossed_needles: 0,
+            score: 0_f64,
+            score_squares: 0_f64,
+        }
+    }
+
+    /// Needles `needle_length` long on lines `parallel_width` apart.
+    ///
+    /// Needles longer than `parallel_width` are allowed and can cross several lines.
+    /// Both must be positive, with a ratio between [`MIN_LENGTH_RATIO`] and [`MAX_LENGTH_RATIO`].
+    pub fn with_lengths(needle_length: f64, parallel_width: f64) -> Result<Self, String> {
+        if !(needle_length > 0_f64 && needle_length.is_finite()) {
+            return Err(format!(
+                "the needle length must be a positive number, not {}",
+                needle_length
+            ));
+        }
+        if !(parallel_width > 0_f64 && parallel_width.is_finite()) {
+            return Err(format!(
+                "the line spacing must be a positive number, not {}",
+                parallel_width
+            ));
+        }
+        let ratio = needle_length / parallel_width;
+        if !(MIN_LENGTH_RATIO..=MAX_LENGTH_RATIO).contains(&ratio) {
+            return Err(format!(
+                "the needle must be between {} and {} times as long as the lines are apart, not {}",
+                MIN_LENGTH_RATIO, MAX_LENGTH_RATIO, ratio
+            ));
+        }
+        Ok(BuffonsNeedle {
+            needle_length,
+            parallel_width,
+            ..Self::new()
+        })
+    }
+
+    /// Drop the needles at the points of `points` instead of pseudo-random ones.
+    pub fn with_points(mut self, points: PointSource) -> Self {
+        self.points = PointCursor::new(points);
+        self
+    }
+
+    /// Score the drops with `reduction` instead of counting crossings.
+    ///
+    /// [`VarianceReduction::ImportanceSampling`] can't be combined with
+    /// [`Direction::Disk`]: importance sampled angles need pi.
+    pub fn with_variance_reduction(mut self, reduction: VarianceReduction) -> Result<Self, String> {
+        self.reduction = reduction;
+        self.check_direction()?;
+        Ok(self)
+    }
+
+    /// Draw the direction of the needles with `direction`, e.g. without using pi.
+    ///
+    /// [`Direction::Disk`] can't be combined with [`VarianceReduction::ImportanceSampling`].
+    pub fn with_direction(mut self, direction: Direction) -> Result<Self, String> {
+        self.direction = direction;
+        self.check_direction()?;
+        Ok(self)
+    }
+
+    fn check_direction(&self) -> Result<(), String> {
+        if self.direction == Direction::Disk
+            && self.reduction == VarianceReduction::ImportanceSampling
+        {
+            return Err("importance sampled angles can't be drawn without pi".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn points(&self) -> PointSource {
+        self.points.source()
+    }
+
+    pub fn variance_reduction(&self) -> VarianceReduction {
+        self.reduction
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Number of times a needle came to rest crossing a line, counting every line a long needle crosses.
+    pub fn crossings(&self) -> u64 {
+        self.crossings
+    }
+
+    /// Number of needles that came to rest crossing at least one line.
+    pub fn crossed_needles(&self) -> u64 {
+        self.crossed_needles
+    }
+
+    /// pi from the share of needles crossing at least one line, without counting the lines.
+    ///
+    /// It inverts the probability (2 / pi) * g(l / t) that a needle crosses a line,
+    /// with g(x) = x for short needles and x - sqrt(x^2 - 1) + arcsec(x) for long ones.
+    /// The angles must be uniform, so it is meaningless with [`VarianceReduction::ImportanceSampling`].
+    pub fn crossed_needle_estimate(&self) -> Estimate {
+        let n = self.drops as f64;
+        let p = self.crossed_needles as f64 / n;
+        let ratio = self.needle_length / self.parallel_width;
+        let g = if ratio <= 1_f64 {
+            ratio
+        } else {
+            ratio - (ratio * ratio - 1_f64).sqrt() + (1_f64 / ratio).acos()
+        };
+        let value = 2_f64 * g / p;
+        let std_error = value * ((1_f64 - p) / (n * p)).sqrt();
+        Estimate::new(value, self.drops, std_error)
+    }
+
+    pub fn needle_length(&self) -> f64 {
+        self.needle_length
+    }
+
+    pub fn parallel_width(&self) -> f64 {
+        self.parallel_width
+    }
+
+    // pi = 2l / (t * p) with p the average score, and its delta method standard error
+    fn score_estimate(&self) -> Estimate {
+        let n = self.drops as f64;
+        let p = self.score / n;
+        let value = (2_f64 * self.needle_length) / (p * self.parallel_width);
+        let variance = (self.score_squares - n * p * p) / (n - 1_f64);
+        let std_error = value * (variance / n).sqrt() / p;
+        Estimate::new(value, self.drops, std_error)
+    }
+
+    /// Drop `n` more needles like [`PiEstimator::sample`], calling `observe` with each one.
+    pub fn sample_with<F>(&mut self, rng: &mut dyn RngCore, n: u64, mut observe: F)
+    where
+        F: FnMut(Needle),
+    {
+        let two_pi = std::f64::consts::TAU;
+
+        for _ in 0..n {
+            // Only care about the x position since the y position doesn't affect the outcome
+            let [u, v] = match self.direction {
+                Direction::Angle => self.points.point(rng),
+                // The direction doesn't come from the points
+                Direction::Disk => {
+                    let [u] = self.points.point(rng);
+                    [u, 0_f64]
+                }
+            };
+            self.points.advance();
+            let needle_start_x = map(u, 0_f64, 1_f64, 0_f64, self.parallel_width);
+
+            // Cosine and sine of the angle of the needle
+            let (cos, sin, weight) = match (self.direction, self.reduction) {
+                (Direction::Disk, _) => {
+                    let (cos, sin) = disk_direction(rng);
+                    (cos, sin, 1_f64)
+                }
+                (Direction::Angle, VarianceReduction::ImportanceSampling) => {
+                    let (angle, weight) = importance_sampled_angle(v);
+                    (f64::cos(angle), f64::sin(angle), weight)
+                }
+                (Direction::Angle, _) => {
+                    let angle = map(v, 0_f64, 1_f64, 0_f64, two_pi);
+                    (f64::cos(angle), f64::sin(angle), 1_f64)
+                }
+            };
+            let needle_end_x = needle_start_x + self.needle_length * cos;
+
+            // If end of needle is outside of width then it has crossed a line
+            let crosses = needle_end_x < 0_f64 || needle_end_x > self.parallel_width;
+            let lines_crossed = if crosses {
+                self.crossed_needles += 1;
+                lines_crossed(needle_end_x, self.parallel_width)
+            } else {
+                0
+            };
+            self.crossings += lines_crossed;
+            let score = match self.reduction {
+                // The expected number of lines crossed at this angle
+                VarianceReduction::RaoBlackwell => {
+                    self.needle_length * cos.abs() / self.parallel_width
+                }
+                _ => lines_crossed as f64 * weight,
+            };
+            self.score += score;
+            self.score_squares += score * score;
+            observe(Needle {
+                start_x: needle_start_x,
+                end_x: needle_end_x,
+                end_y: self.needle_length * sin,
+                crosses,
+            });
+        }
+        self.drops += n;
+    }
+}
+
+// Number of lines between a needle starting in [0, t) and its end at `end_x`,
+// lines being at every multiple of t
+fn lines_crossed(end_x: f64, parallel_width: f64) -> u64 {
+    let lines = if end_x < 0_f64 {
+        (-end_x / parallel_width).floor() + 1_f64
+    } else {
+        (end_x / parallel_width).ceil() - 1_f64
+    };
+    lines.max(0_f64) as u64
+}
+
+// Cosine and sine of a direction uniform on the circle, found without pi: a
+// point uniform in the unit disk, drawn by rejection from the square around it,
+// scaled to length 1
+pub(crate) fn disk_direction(rng: &mut dyn RngCore) -> (f64, f64) {
+    loop {
+        let x = rng.gen_range(-1_f64, 1_f64);
+        let y = rng.gen_range(-1_f64, 1_f64);
+        let length_squared = x * x + y * y;
+        // The centre itself has no direction
+        if length_squared <= 1_f64 && length_squared > 0_f64 {
+            let length = length_squared.sqrt();
+            return (x / length, y / length);
+        }
+    }
+}
+
+// An angle drawn from `v`, uniform in [0, 1), more often across the lines than
+// along them, and the ratio of its uniform density to the density it was drawn
+// from. Folded into a quarter turn, the angle is w * pi / 2 away from lying
+// across the lines, where w has the density 3/2 - w: a straight line standing in
+// for the best proposal, proportional to the square root of the crossing
+// probability cos(w * pi / 2).
+fn importance_sampled_angle(v: f64) -> (f64, f64) {
+    let quadrant = (4_f64 * v).floor();
+    // Invert the distribution function 3w/2 - w^2/2
+    let uniform = 4_f64 * v - quadrant;
+    let w = (3_f64 - (9_f64 - 8_f64 * uniform).sqrt()) / 2_f64;
+    // Odd quadrants go backwards, so the needle is across the lines at both ends of each
+    let turned = if (quadrant as u32).is_multiple_of(2) {
+        w
+    } else {
+        1_f64 - w
+    };
+    (
+        (quadrant + turned) * std::f64::consts::FRAC_PI_2,
+        1_f64 / (1.5_f64 - w),
+    )
+}
+
+impl Default for BuffonsNeedle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PiEstimator for BuffonsNeedle {
+    fn name(&self) -> String {
+        // A variance reduction mode is named first, then a pi-free direction, then the points
+        let reduction =
+            (self.reduction != VarianceReduction::Crossings).then_some(self.reduction.name());
+        let pi_free = (self.direction == Direction::Disk).then_some("pi-free");
+        let points = self.points.source().qualifier();
+        qualified_name("buffons needle", &[reduction, pi_free, points])
+    }
+
+    fn sample(&mut self, rng: &mut dyn RngCore, n: u64) {
+        self.sample_with(rng, n, |_| {});
+    }
+
+    fn estimate(&self) -> Estimate {
+        // Only short needles cross at most one line, making the crossings binomial
+        if self.reduction != VarianceReduction::Crossings
+            || self.needle_length > self.parallel_width
+        {
+            return self.score_estimate();
+        }
+        let n = self.drops as f64;
+        let value =
+            (2_f64 * n * self.needle_length) / (self.crossings as f64 * self.parallel_width);
+
+        // Delta method: pi = 2l / (t * p) with p = x / n the crossing proportion,
+        // so se(pi) = pi * se(p) / p = pi * sqrt((1 - p) / (n * p))
+        let p = self.crossings as f64 / n;
+        let std_error = value * ((1_f64 - p) / (n * p)).sqrt();
+
+        Estimate::new(value, self.drops, std_error)
+    }
+
+    fn sample_count(&self) -> u64 {
+        self.drops
+    }
+
+    fn reset(&mut self) {
+        self.points.skip_to(0);
+        self.drops = 0;
+        self.crossings = 0;
+        self.crossed_needles = 0;
+        self.score = 0_f64;
+        self.score_squares = 0_f64;
+    }
+
+    fn skip_to(&mut self, index: u64) {
+        self.points.skip_to(index);
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.points.merge(&other.points);
+        self.drops += other.drops;
+        self.crossings += other.crossings;
+        self.crossed_needles += other.crossed_needles;
+        self.score += other.score;
+        self.score_squares += other.score_squares;
+    }
+}
+
+/// Approximate pi by dropping `iterations` needles on a set of parallel lines, drawing from `rng`.
+pub fn buffons_needle<R: Rng>(rng: &mut R, iterations: u64) -> Estimate {
+    let mut estimator = BuffonsNeedle::new();
+    estimator.sample(rng, iterations);
+    estimator.estimate()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::seeded_rng;
+
+    #[test]
+    fn seeded_estimate_is_exact() {
+        let estimate = buffons_needle(&mut seeded_rng(1), 10_000);
+        assert_eq!(estimate.value, 3.1333228889237037);
+        assert_eq!(estimate.samples, 10_000);
+    }
+
+    #[test]
+    fn reset_forgets_every_needle() {
+        let mut estimator = BuffonsNeedle::new();
+        estimator.sample(&mut seeded_rng(1), 10_000);
+        estimator.reset();
+        estimator.sample(&mut seeded_rng(1), 10_000);
+        assert_eq!(
+            estimator.estimate(),
+            buffons_needle(&mut seeded_rng(1), 10_000)
+        );
+    }
+
+    #[test]
+    fn long_needles_cross_every_line_they_reach() {
+        assert_eq!(lines_crossed(0.5, 1.0), 0);
+        assert_eq!(lines_crossed(1.5, 1.0), 1);
+        assert_eq!(lines_crossed(3.2, 1.0), 3);
+        assert_eq!(lines_crossed(-0.1, 1.0), 1);
+        assert_eq!(lines_crossed(-2.5, 1.0), 3);
+        assert_eq!(lines_crossed(5.0, 2.0), 2);
+    }
+
+    #[test]
+    fn long_needles_estimate_pi() {
+        let mut estimator = BuffonsNeedle::with_lengths(3.0, 2.0).unwrap();
+        estimator.sample(&mut seeded_rng(1), 100_000);
+        for estimate in [estimator.estimate(), estimator.crossed_needle_estimate()] {
+            let error = (estimate.value - std::f64::consts::PI).abs();
+            assert!(error < 4.0 * estimate.std_error, "{}", estimate);
+        }
+    }
+
+    #[test]
+    fn variance_reductions_estimate_pi() {
+        let reductions = [
+            VarianceReduction::RaoBlackwell,
+            VarianceReduction::ImportanceSampling,
+        ];
+        for &(needle_length, parallel_width) in &[(1.0, 1.0), (3.0, 2.0)] {
+            for &reduction in &reductions {
+                let mut estimator = BuffonsNeedle::with_lengths(needle_length, parallel_width)
+                    .and_then(|estimator| estimator.with_variance_reduction(reduction))
+                    .unwrap();
+                estimator.sample(&mut seeded_rng(1), 100_000);
+                let estimate = estimator.estimate();
+                let error = (estimate.value - std::f64::consts::PI).abs();
+                assert!(
+                    error < 4.0 * estimate.std_error,
+                    "{}: {}",
+                    estimator.name(),
+                    estimate
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn importance_weights_average_to_one() {
+        // The midpoints of a fine grid of v stand in for a uniform v
+        let n = 100_000;
+        let mut weights = 0.0;
+        for i in 0..n {
+            let v = (i as f64 + 0.5) / n as f64;
+            let (angle, weight) = importance_sampled_angle(v);
+            assert!((0.0..std::f64::consts::TAU).contains(&angle), "{}", angle);
+            weights += weight;
+        }
+        assert!((weights / n as f64 - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn disk_directions_are_unit_vectors() {
+        let mut rng = seeded_rng(1);
+        for _ in 0..1_000 {
+            let (cos, sin) = disk_direction(&mut rng);
+            assert!((cos * cos + sin * sin - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn pi_free_seeded_estimate_is_exact() {
+        let mut estimator = BuffonsNeedle::new()
+            .with_direction(Direction::Disk)
+            .unwrap();
+        estimator.sample(&mut seeded_rng(1), 10_000);
+        assert_eq!(estimator.estimate().value, 3.157562361856647);
+        assert_eq!(estimator.name(), "buffons needle (pi-free)");
+    }
+
+    #[test]
+    fn importance_sampling_needs_pi() {
+        let needle = BuffonsNeedle::new()
+            .with_direction(Direction::Disk)
+            .unwrap();
+        assert!(needle
+            .with_variance_reduction(VarianceReduction::ImportanceSampling)
+            .is_err());
+        let needle = BuffonsNeedle::new()
+            .with_variance_reduction(VarianceReduction::ImportanceSampling)
+            .unwrap();
+        assert!(needle.with_direction(Direction::Disk).is_err());
+    }
+
+    #[test]
+    fn lengths_are_checked() {
+        assert!(BuffonsNeedle::with_lengths(1.0, 0.0).is_err());
+        assert!(BuffonsNeedle::with_lengths(-1.0, 1.0).is_err());
+        assert!(BuffonsNeedle::with_lengths(f64::NAN, 1.0).is_err());
+        assert!(BuffonsNeedle::with_lengths(1e-4, 1.0).is_err());
+        assert!(BuffonsNeedle::with_lengths(2e3, 1.0).is_err());
+        assert!(BuffonsNeedle::with_lengths(10.0, 1.0).is_ok());
+    }
+}
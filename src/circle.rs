@@ -0,0 +1,578 @@
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+
+use crate::estimator::{qualified_name, Estimate, PiEstimator};
+use crate::map;
+use crate::parallel::BLOCK_SIZE;
+use crate::points::{PointCursor, PointSource};
+
+// Monte carlo method for random points inside a circle:
+// 1. Have a circle enclosed by a square with sides equal to the diameter of the circle
+// 2. Generate a random set of points on the square
+// 3. Area of the circle is pi * r^2 with r = 0.5
+// 4. Area of square = 1 x 1
+// 5. Divide area of circle by square we get pi / 4
+// 6. pi / 4 ~ Ncircle / Ntotal
+// 7. pi ~ 4 * Ncircle / Ntotal
+
+/// Where a dart landed, as a point in `[-1, 1] x [-1, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dart {
+    pub x: f64,
+    pub y: f64,
+    pub inside: bool,
+}
+
+/// Where on the square the darts can land.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sampling {
+    /// Anywhere in the square, using continuous `f64` coordinates.
+    Continuous,
+    /// Only on the top left corner of each pixel of a `width` x `height` grid.
+    ///
+    /// This is how the first visuals threw darts. It is biased: the estimate
+    /// converges to 4 * (grid points inside the circle) / (grid points), not
+    /// to pi, so no number of darts gets it closer than [`pixel_grid_bias`].
+    Pixels { width: u32, height: u32 },
+}
+
+// Mean of the control x^2 + y^2 over the square: 1/3 + 1/3
+const CONTROL_MEAN: f64 = 2_f64 / 3_f64;
+
+/// How the darts are combined into an estimate, see [`CircleInSquare::with_variance_reduction`].
+///
+/// Every mode but [`VarianceReduction::HitOrMiss`] throws the darts in groups
+/// and averages one unbiased value of pi per group. The estimate and its
+/// standard error only use finished groups.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VarianceReduction {
+    /// Every dart on its own: 4 if it lands inside the circle, 0 otherwise.
+    HitOrMiss,
+    /// Every dart is followed by its mirror image `(±(1 - |x|), ±(1 - |y|))` in
+    /// the same quadrant, so a dart near the centre is paired with one near the
+    /// corner and their hits are negatively correlated.
+    Antithetic,
+    /// The square is cut into `strata` x `strata` cells and each group throws one dart in every cell.
+    ///
+    /// Where a dart lands inside its cell is always pseudo-random: quasi-random
+    /// points follow each other in step with the cells and would miss parts of them.
+    Stratified { strata: u32 },
+    /// Each group of `points` darts has exactly one dart in every row and every
+    /// column of a `points` x `points` grid.
+    ///
+    /// As with [`VarianceReduction::Stratified`], the darts land at pseudo-random
+    /// points of their cells.
+    LatinHypercube { points: u32 },
+    /// Each dart scores 4 * sqrt(1 - x^2), four times the height of the circle
+    /// above its `x`, whether it lands inside or not. Its mean is the area of the circle.
+    MeanValue,
+    /// Hit-or-miss corrected by the control `x^2 + y^2`, whose mean 2/3 is
+    /// known, with the best coefficient estimated from the darts.
+    ControlVariate,
+}
+
+impl VarianceReduction {
+    /// Name used in the output, e.g. `latin hypercube`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            VarianceReduction::HitOrMiss => "hit or miss",
+            VarianceReduction::Antithetic => "antithetic",
+            VarianceReduction::Stratified { .. } => "stratified",
+            VarianceReduction::LatinHypercube { .. } => "latin hypercube",
+            VarianceReduction::MeanValue => "mean value",
+            VarianceReduction::ControlVariate => "control variate",
+        }
+    }
+
+    /// Whether the darts are placed by the [`PointSource`] of the estimator.
+    pub fn uses_points(&self) -> bool {
+        !matches!(
+            self,
+            VarianceReduction::Stratified { .. } | VarianceReduction::LatinHypercube { .. }
+        )
+    }
+
+    /// Number of darts in each group.
+    pub fn group_size(&self) -> u64 {
+        match *self {
+            VarianceReduction::Antithetic => 2,
+            VarianceReduction::Stratified { strata } => strata as u64 * strata as u64,
+            VarianceReduction::LatinHypercube { points } => points as u64,
+            _ => 1,
+        }
+    }
+}
+
+// Running sums over the finished groups of darts: the value y of each group
+// and, for the control variate, its control c
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct GroupSums {
+    groups: u64,
+    y: f64,
+    yy: f64,
+    c: f64,
+    cc: f64,
+    yc: f64,
+}
+
+impl GroupSums {
+    fn add(&mut self, y: f64, c: f64) {
+        self.groups += 1;
+        self.y += y;
+        self.yy += y * y;
+        self.c += c;
+        self.cc += c * c;
+        self.yc += y * c;
+    }
+
+    fn merge(&mut self, other: &GroupSums) {
+        self.groups += other.groups;
+        self.y += other.y;
+        self.yy += other.yy;
+        self.c += other.c;
+        self.cc += other.cc;
+        self.yc += other.yc;
+    }
+}
+
+/// Throw darts at a square and count how many land inside its inscribed circle.
+///
+/// One sample is one dart. Darts land at pseudo-random points unless another
+/// [`PointSource`] is chosen with [`CircleInSquare::with_points`], and count
+/// on their own unless a [`VarianceReduction`] is chosen.
+#[derive(Debug, Clone)]
+pub struct CircleInSquare {
+    sampling: Sampling,
+    points: PointCursor,
+    reduction: VarianceReduction,
+    inside: u64,
+    total: u64,
+    sums: GroupSums,
+    // The group being thrown: its darts so far, their total value, its first
+    // dart (mirrored by the antithetic mode) and the row of each column (for
+    // the Latin hypercube)
+    group_darts: u64,
+    group_value: f64,
+    first_dart: (f64, f64),
+    rows: Vec<u32>,
+}
+
+impl CircleInSquare {
+    /// Darts land anywhere in the square.
+    pub fn new() -> Self {
+        Self::with_sampling(Sampling::Continuous)
+    }
+
+    /// Darts only land on a `width` x `height` grid of pixels, see [`Sampling::Pixels`].
+    pub fn pixels(width: u32, height: u32) -> Self {
+        Self::with_sampling(Sampling::Pixels { width, height })
+    }
+
+    pub fn with_sampling(sampling: Sampling) -> Self {
+        CircleInSquare {
+            sampling,
+            points: PointCursor::default(),
+            reduction: VarianceReduction::HitOrMiss,
+            inside: 0,
+            total: 0,
+            sums: GroupSums::default(),
+            group_darts: 0,
+            group_value: 0_f64,
+            first_dart: (0_f64, 0_f64),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Throw the darts at the points of `points` instead of pseudo-random ones.
+    pub fn with_points(mut self, points: PointSource) -> Self {
+        self.points = PointCursor::new(points);
+        self
+    }
+
+    /// Combine the darts with `reduction` instead of counting them one by one.
+    ///
+    /// A group of `reduction` must be a power of two up to
+    /// [`BLOCK_SIZE`](crate::parallel::BLOCK_SIZE) darts, not e.g. 3 strata, so
+    /// that groups don't straddle the blocks of a [`ParallelSampler`](crate::ParallelSampler).
+    pub fn with_variance_reduction(mut self, reduction: VarianceReduction) -> Result<Self, String> {
+        let group_size = reduction.group_size();
+        if !(group_size.is_power_of_two() && group_size <= BLOCK_SIZE) {
+            return Err(format!(
+                "groups of {} darts must be a power of two up to {}",
+                group_size, BLOCK_SIZE
+            ));
+        }
+        self.reduction = reduction;
+        Ok(self)
+    }
+
+    pub fn sampling(&self) -> Sampling {
+        self.sampling
+    }
+
+    pub fn points(&self) -> PointSource {
+        self.points.source()
+    }
+
+    pub fn variance_reduction(&self) -> VarianceReduction {
+        self.reduction
+    }
+
+    /// Number of darts that landed inside the circle.
+    pub fn inside_count(&self) -> u64 {
+        self.inside
+    }
+
+    /// Draw `n` more darts like [`PiEstimator::sample`], calling `observe` with each one.
+    ///
+    /// This is how the visuals follow the estimator without slowing down headless runs.
+    pub fn sample_with<F>(&mut self, rng: &mut dyn RngCore, n: u64, mut observe: F)
+    where
+        F: FnMut(Dart),
+    {
+        for _ in 0..n {
+            // Points between -1 and 1 (circle of radius 1 with center [0, 0])
+            let (point_x, point_y) = match (self.reduction, self.sampling) {
+                (VarianceReduction::HitOrMiss, Sampling::Continuous) => {
+                    let [u, v] = self.points.point(rng);
+                    (map(u, 0.0, 1.0, -1.0, 1.0), map(v, 0.0, 1.0, -1.0, 1.0))
+                }
+                (VarianceReduction::HitOrMiss, Sampling::Pixels { width, height }) => {
+                    let (pos_x, pos_y) = if self.points.source().is_quasi_random() {
+                        let [u, v] = self.points.point(rng);
+                        ((u * width as f64) as u32, (v * height as f64) as u32)
+                    } else {
+                        (rng.gen_range(0, width), rng.gen_range(0, height))
+                    };
+
+                    // Map pos_x and pos_y between -1 and 1
+                    (
+                        map(pos_x as f64, 0.0, width as f64, -1.0, 1.0),
+                        map(pos_y as f64, 0.0, height as f64, -1.0, 1.0),
+                    )
+                }
+                _ => self.group_dart(rng),
+            };
+
+            let inside = is_inside_circle(point_x, point_y);
+            if inside {
+                self.inside += 1;
+            }
+            if self.reduction != VarianceReduction::HitOrMiss {
+                self.add_to_group(point_x, point_y, inside);
+            }
+            self.points.advance();
+            observe(Dart {
+                x: point_x,
+                y: point_y,
+                inside,
+            });
+        }
+        self.total += n;
+    }
+
+    // Next dart of the group being thrown by a variance reduction mode
+    fn group_dart(&mut self, rng: &mut dyn RngCore) -> (f64, f64) {
+        let dart = self.group_darts;
+        match self.reduction {
+            VarianceReduction::Antithetic if dart == 1 => {
+                let mirror = |z: f64| z.signum() * (1_f64 - z.abs());
+                (mirror(self.first_dart.0), mirror(self.first_dart.1))
+            }
+            VarianceReduction::Stratified { strata } => {
+                let (u, v) = (rng.gen_range(0_f64, 1_f64), rng.gen_range(0_f64, 1_f64));
+                let (column, row) = (dart % strata as u64, dart / strata as u64);
+                self.land(
+                    (column as f64 + u) / strata as f64,
+                    (row as f64 + v) / strata as f64,
+                )
+            }
+            VarianceReduction::LatinHypercube { points } => {
+                if dart == 0 {
+                    // Start every group from the same order, so that the rows only
+                    // depend on the rng and not on how the darts were batched
+                    self.rows.clear();
+                    self.rows.extend(0..points);
+                    self.rows.shuffle(rng);
+                }
+                let (u, v) = (rng.gen_range(0_f64, 1_f64), rng.gen_range(0_f64, 1_f64));
+                let row = self.rows[dart as usize];
+                self.land(
+                    (dart as f64 + u) / points as f64,
+                    (row as f64 + v) / points as f64,
+                )
+            }
+            _ => {
+                let [u, v] = self.points.point(rng);
+                self.land(u, v)
+            }
+        }
+    }
+
+    // Map a point of [0, 1) x [0, 1) to where the dart lands in the square
+    fn land(&self, u: f64, v: f64) -> (f64, f64) {
+        let (u, v) = match self.sampling {
+            Sampling::Continuous => (u, v),
+            Sampling::Pixels { width, height } => (
+                (u * width as f64).floor() / width as f64,
+                (v * height as f64).floor() / height as f64,
+            ),
+        };
+        (map(u, 0.0, 1.0, -1.0, 1.0), map(v, 0.0, 1.0, -1.0, 1.0))
+    }
+
+    fn add_to_group(&mut self, point_x: f64, point_y: f64, inside: bool) {
+        let value = match self.reduction {
+            // Only x counts, however far up the circle is above it
+            VarianceReduction::MeanValue => 4_f64 * (1_f64 - point_x * point_x).max(0_f64).sqrt(),
+            _ if inside => 4_f64,
+            _ => 0_f64,
+        };
+        if self.group_darts == 0 {
+            self.first_dart = (point_x, point_y);
+        }
+        self.group_darts += 1;
+        self.group_value += value;
+
+        if self.group_darts == self.reduction.group_size() {
+            let control = match self.reduction {
+                VarianceReduction::ControlVariate => point_x * point_x + point_y * point_y,
+                _ => 0_f64,
+            };
+            self.sums
+                .add(self.group_value / self.group_darts as f64, control);
+            self.group_darts = 0;
+            self.group_value = 0_f64;
+        }
+    }
+
+    // Mean of the group values, or of the values corrected by the control
+    fn group_estimate(&self) -> Estimate {
+        let sums = &self.sums;
+        let groups = sums.groups as f64;
+        let mean_y = sums.y / groups;
+        let variance_y = (sums.yy - groups * mean_y * mean_y) / (groups - 1_f64);
+
+        let (value, variance) = if self.reduction == VarianceReduction::ControlVariate {
+            // y - beta (c - E[c]) with beta = cov(y, c) / var(c) leaves var(y) - beta cov(y, c)
+            let mean_c = sums.c / groups;
+            let variance_c = (sums.cc - groups * mean_c * mean_c) / (groups - 1_f64);
+            let covariance = (sums.yc - groups * mean_y * mean_c) / (groups - 1_f64);
+            let beta = covariance / variance_c;
+            (
+                mean_y - beta * (mean_c - CONTROL_MEAN),
+                variance_y - beta * covariance,
+            )
+        } else {
+            (mean_y, variance_y)
+        };
+
+        Estimate::new(
+            value,
+            sums.groups * self.reduction.group_size(),
+            (variance / groups).sqrt(),
+        )
+    }
+}
+
+impl Default for CircleInSquare {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PiEstimator for CircleInSquare {
+    fn name(&self) -> String {
+        // A variance reduction mode is named first, then the points if it uses them
+        let reduction =
+            (self.reduction != VarianceReduction::HitOrMiss).then_some(self.reduction.name());
+        let points = self
+            .points
+            .source()
+            .qualifier()
+            .filter(|_| self.reduction.uses_points());
+        qualified_name("circle inside square", &[reduction, points])
+    }
+
+    fn sample(&mut self, rng: &mut dyn RngCore, n: u64) {
+        self.sample_with(rng, n, |_| {});
+    }
+
+    fn estimate(&self) -> Estimate {
+        match self.reduction {
+            VarianceReduction::HitOrMiss => estimate_from_counts(self.inside, self.total),
+            _ => self.group_estimate(),
+        }
+    }
+
+    fn sample_count(&self) -> u64 {
+        self.total
+    }
+
+    fn reset(&mut self) {
+        self.points.skip_to(0);
+        self.inside = 0;
+        self.total = 0;
+        self.sums = GroupSums::default();
+        self.group_darts = 0;
+        self.group_value = 0_f64;
+    }
+
+    fn skip_to(&mut self, index: u64) {
+        self.points.skip_to(index);
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.points.merge(&other.points);
+        self.inside += other.inside;
+        self.total += other.total;
+        self.sums.merge(&other.sums);
+    }
+}
+
+/// Whether a point in `[-1, 1] x [-1, 1]` lies inside the circle of radius 1 centred on the origin.
+pub fn is_inside_circle(point_x: f64, point_y: f64) -> bool {
+    // If Euclidean distance is less than the radius than it's inside the circle
+    let square_of_radius = 1_f64; // square of 1 is 1
+    point_x.powi(2) + point_y.powi(2) < square_of_radius
+}
+
+/// pi ~ 4 * Ncircle / Ntotal
+pub fn pi_from_counts(inside: u64, total: u64) -> f64 {
+    (4_f64 * inside as f64) / (total as f64)
+}
+
+/// pi ~ 4 * Ncircle / Ntotal, with the binomial standard error 4 * sqrt(p * (1 - p) / Ntotal).
+pub fn estimate_from_counts(inside: u64, total: u64) -> Estimate {
+    let p = inside as f64 / total as f64;
+    let std_error = 4_f64 * (p * (1_f64 - p) / total as f64).sqrt();
+    Estimate::new(pi_from_counts(inside, total), total, std_error)
+}
+
+/// How far the [`Sampling::Pixels`] estimate is from pi after infinitely many darts.
+///
+/// This is the exact limit 4 * (grid points inside the circle) / (grid points) minus pi.
+pub fn pixel_grid_bias(width: u32, height: u32) -> f64 {
+    let rows = height as i64;
+    let mut inside = 0_u64;
+    for pos_x in 0..width {
+        let point_x = map(pos_x as f64, 0.0, width as f64, -1.0, 1.0);
+        let is_inside = |pos_y: i64| {
+            let point_y = map(pos_y as f64, 0.0, height as f64, -1.0, 1.0);
+            (0..rows).contains(&pos_y) && is_inside_circle(point_x, point_y)
+        };
+        // The rows inside the circle are those between the heights -limit and
+        // limit. Rounding can put them a row off, so the ends are moved onto the
+        // first row inside and the first one past it.
+        let limit = (1_f64 - point_x * point_x).max(0_f64).sqrt();
+        let row_at = |point_y: f64| {
+            let pos_y = map(point_y, -1.0, 1.0, 0.0, height as f64).ceil() as i64;
+            pos_y.clamp(0, rows)
+        };
+        let mut low = row_at(-limit);
+        while low > 0 && is_inside(low - 1) {
+            low -= 1;
+        }
+        while low < rows && !is_inside(low) {
+            low += 1;
+        }
+        let mut high = row_at(limit).max(low);
+        while high < rows && is_inside(high) {
+            high += 1;
+        }
+        while high > low && !is_inside(high - 1) {
+            high -= 1;
+        }
+        inside += (high - low) as u64;
+    }
+    pi_from_counts(inside, width as u64 * height as u64) - std::f64::consts::PI
+}
+
+/// Approximate pi by throwing `iterations` darts at the square, drawing from `rng`.
+pub fn circle_inside_square<R: Rng>(rng: &mut R, iterations: u64) -> Estimate {
+    let mut estimator = CircleInSquare::default();
+    estimator.sample(rng, iterations);
+    estimator.estimate()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::seeded_rng;
+
+    #[test]
+    fn seeded_estimate_is_exact() {
+        let estimate = circle_inside_square(&mut seeded_rng(1), 10_000);
+        assert_eq!(estimate.value, 3.1328);
+        assert_eq!(estimate.samples, 10_000);
+    }
+
+    #[test]
+    fn variance_reductions_estimate_pi() {
+        let reductions = [
+            VarianceReduction::HitOrMiss,
+            VarianceReduction::Antithetic,
+            VarianceReduction::Stratified { strata: 16 },
+            VarianceReduction::LatinHypercube { points: 256 },
+            VarianceReduction::MeanValue,
+            VarianceReduction::ControlVariate,
+        ];
+        for &reduction in &reductions {
+            let mut estimator = CircleInSquare::new()
+                .with_variance_reduction(reduction)
+                .unwrap();
+            estimator.sample(&mut seeded_rng(1), 1 << 17);
+            let estimate = estimator.estimate();
+            let error = (estimate.value - std::f64::consts::PI).abs();
+            assert!(
+                error < 4.0 * estimate.std_error,
+                "{}: {}",
+                estimator.name(),
+                estimate
+            );
+        }
+    }
+
+    #[test]
+    fn groups_must_be_a_power_of_two() {
+        let estimator = CircleInSquare::new();
+        assert!(estimator
+            .clone()
+            .with_variance_reduction(VarianceReduction::Stratified { strata: 3 })
+            .is_err());
+        assert!(estimator
+            .with_variance_reduction(VarianceReduction::Stratified { strata: 4 })
+            .is_ok());
+    }
+
+    #[test]
+    fn pixel_grid_bias_counts_every_pixel_inside() {
+        // Rows and columns at -1, -0.5, 0 and 0.5: the nine pixels away from -1 are inside
+        assert_eq!(pixel_grid_bias(4, 4), 2.25 - std::f64::consts::PI);
+        for width in 1..40 {
+            for height in 1..40 {
+                let mut inside = 0;
+                for pos_x in 0..width {
+                    for pos_y in 0..height {
+                        let point_x = map(pos_x as f64, 0.0, width as f64, -1.0, 1.0);
+                        let point_y = map(pos_y as f64, 0.0, height as f64, -1.0, 1.0);
+                        if is_inside_circle(point_x, point_y) {
+                            inside += 1;
+                        }
+                    }
+                }
+                let bias =
+                    pi_from_counts(inside, width as u64 * height as u64) - std::f64::consts::PI;
+                assert_eq!(pixel_grid_bias(width, height), bias, "{}x{}", width, height);
+            }
+        }
+    }
+
+    #[test]
+    fn counts_give_pi_and_binomial_error() {
+        assert_eq!(pi_from_counts(3, 4), 3.0);
+        let estimate = estimate_from_counts(1, 2);
+        assert_eq!(estimate.value, 2.0);
+        assert_eq!(estimate.std_error, 4.0 * (0.25_f64 / 2.0).sqrt());
+    }
+}
@@ -0,0 +1,505 @@
+// Command line parsing for the binary
+
+use std::path::{Path, PathBuf};
+
+use approximating_pi::buffon::{self, BuffonsNeedle};
+use approximating_pi::circle::VarianceReduction;
+use approximating_pi::laplace::BuffonLaplace;
+use approximating_pi::noodle::Noodle;
+use approximating_pi::parallel::default_threads;
+use approximating_pi::points::PointSource;
+use approximating_pi::render::circle::{HIT_COLOR, MISS_COLOR};
+use approximating_pi::render::{parse_color, Layout, Rate};
+use image::Rgba;
+
+pub const USAGE: &str = "\
+Approximating pi using Monte Carlo methods
+
+USAGE:
+    approximating-pi [METHOD] [OPTIONS]
+
+METHODS:
+    walk      1-D random walks (visualised)
+    buffon    Buffon's needle (visualised)
+    laplace   Buffon-Laplace needles on a grid (visualised)
+    noodle    Buffon's noodle: curves dropped on parallel lines (visualised)
+    circle    Random points inside a circle (visualised)
+    all       Every method (default)
+
+OPTIONS:
+    -n, --samples <N>    Number of samples (walks, needles or darts) for each method
+        --steps <N>      Steps in each random walk [default: 100]
+        --asymptotic     Use the original, biased sqrt(2n / pi) formula for random walks
+        --needle-length <L>
+                         Length of Buffon's needles, longer than the line spacing if wanted,
+                         and of the Buffon-Laplace ones, no longer than a grid cell [default: 1]
+        --line-spacing <T>
+                         Distance between the lines of Buffon's needle, and between the
+                         vertical lines of the Buffon-Laplace grid [default: 1]
+        --cell-height <B>
+                         Distance between the horizontal lines of the Buffon-Laplace grid
+                         [default: the line spacing]
+        --noodle <SHAPE> Shape of Buffon's noodle: circle, arc, polygon (random, from the seed)
+                         or a file with one 'x y' vertex per line [default: polygon]
+        --pi-free        Point Buffon's needles, the grid's needles and the noodles in the
+                         direction of a point drawn in the unit disk by rejection, instead of
+                         at an angle in [0, 2 pi)
+        --pixel-grid <N> Throw circle darts on an N x N pixel grid (biased) instead of anywhere
+        --points <SRC>   Points behind the circle darts, Buffon's needles, the grid and the
+                         noodles: random, halton, sobol or hammersley [default: random]
+        --compare-points Compare how fast the error of the circle, the needles, the grid and
+                         the noodles shrinks with each kind of points, instead of running
+                         them once
+        --variance-reduction <MODE>
+                         How the circle darts are combined: hit-or-miss, antithetic,
+                         stratified (16 x 16 cells), latin-hypercube (groups of 256),
+                         mean-value or control-variate [default: hit-or-miss];
+                         or how Buffon's needles are scored: crossings, rao-blackwell or
+                         importance-sampling [default: crossings]. Give it twice for both
+        --compare-variance
+                         Run the circle and Buffon's needle with every variance reduction and
+                         report how much each one reduces the variance of a sample, instead
+                         of running them once
+        --seed <N>       Seed to replay a previous run
+    -j, --threads <N>    Number of worker threads [default: number of CPUs]
+    -f, --format <FMT>   Output format: text, csv or json [default: text]
+        --trace <FILE>   Write the convergence of each method to FILE (.json for JSON, otherwise CSV)
+        --trace-points <N>
+                         Checkpoints per power of ten in the trace [default: 10]
+        --render <PATH>  Save the visuals instead of showing them: an animated GIF if PATH ends in .gif,
+                         otherwise numbered PNG frames in the directory PATH
+        --frames <N>     Number of frames to render [default: 100]
+        --frame-step <N> Frames the visuals advance between rendered frames [default: 1]
+        --size <WxH>     Size of the visuals window or rendered frames [default: 512x540]
+        --rate <N>       Samples the visuals draw each frame, or each second with N/s
+                         [default: 1, more steps for long random walks]
+        --hit-color <RRGGBB>
+                         Colour of the circle darts landing inside the circle [default: ff0000]
+        --miss-color <RRGGBB>
+                         Colour of the circle darts landing outside the circle [default: 0000ff]
+        --no-gui         Don't open the visuals window
+    -h, --help           Print this message
+";
+
+// Cells on each side of the square for stratified darts
+pub const STRATA: u32 = 16;
+// Darts in each Latin hypercube
+pub const LATIN_HYPERCUBE_POINTS: u32 = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    Walk,
+    Buffon,
+    Laplace,
+    Noodle,
+    Circle,
+    All,
+}
+
+impl Method {
+    pub fn includes(self, other: Method) -> bool {
+        self == Method::All || self == other
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+/// Shape of Buffon's noodle.
+#[derive(Debug, Clone)]
+pub enum NoodleShape {
+    // Both built with pi
+    Circle,
+    Arc,
+    // Drawn from the seed of the run, without pi
+    Polygon,
+    // Read from a file
+    Vertices(Noodle),
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub method: Method,
+    pub samples: Option<u64>,
+    pub steps: u64,
+    pub asymptotic: bool,
+    pub needle_length: f64,
+    pub line_spacing: f64,
+    pub cell_height: Option<f64>,
+    pub noodle: NoodleShape,
+    pub pi_free: bool,
+    pub pixel_grid: Option<u32>,
+    // Hammersley sets are sized by the number of samples once it is known
+    pub points: PointSource,
+    pub compare_points: bool,
+    pub variance_reduction: VarianceReduction,
+    pub buffon_reduction: buffon::VarianceReduction,
+    pub compare_variance: bool,
+    pub seed: Option<u64>,
+    pub threads: usize,
+    pub format: Format,
+    pub trace: Option<PathBuf>,
+    pub trace_points: u32,
+    pub render: Option<PathBuf>,
+    pub frames: u32,
+    pub frame_step: u32,
+    pub size: Layout,
+    pub rate: Option<Rate>,
+    pub hit_color: Rgba<u8>,
+    pub miss_color: Rgba<u8>,
+    pub gui: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            method: Method::All,
+            samples: None,
+            steps: 100,
+            asymptotic: false,
+            needle_length: 1.0,
+            line_spacing: 1.0,
+            cell_height: None,
+            noodle: NoodleShape::Polygon,
+            pi_free: false,
+            pixel_grid: None,
+            points: PointSource::Random,
+            compare_points: false,
+            variance_reduction: VarianceReduction::HitOrMiss,
+            buffon_reduction: buffon::VarianceReduction::Crossings,
+            compare_variance: false,
+            seed: None,
+            threads: default_threads(),
+            format: Format::Text,
+            trace: None,
+            trace_points: 10,
+            render: None,
+            frames: 100,
+            frame_step: 1,
+            size: Layout::default(),
+            rate: None,
+            hit_color: HIT_COLOR,
+            miss_color: MISS_COLOR,
+            gui: true,
+        }
+    }
+}
+
+impl Options {
+    /// Height of the Buffon-Laplace grid cells.
+    pub fn cell_height(&self) -> f64 {
+        self.cell_height.unwrap_or(self.line_spacing)
+    }
+
+    /// Whether the Buffon-Laplace grid is run, see [`Options::laplace_skipped`].
+    pub fn runs_laplace(&self) -> bool {
+        self.method.includes(Method::Laplace) && self.laplace_skipped().is_none()
+    }
+
+    /// Why `all` leaves out the Buffon-Laplace grid: its needles can't be
+    /// longer than its cells, while Buffon's needles can.
+    pub fn laplace_skipped(&self) -> Option<String> {
+        if self.method != Method::All {
+            return None;
+        }
+        BuffonLaplace::with_lengths(self.needle_length, self.line_spacing, self.cell_height()).err()
+    }
+}
+
+pub enum Command {
+    Run(Box<Options>),
+    Help,
+}
+
+pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut options = Options::default();
+    let mut method = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-n" | "--samples" => options.samples = Some(value(&arg, args.next())?),
+            "--steps" => options.steps = value(&arg, args.next())?,
+            "--asymptotic" => options.asymptotic = true,
+            "--needle-length" => options.needle_length = value(&arg, args.next())?,
+            "--line-spacing" => options.line_spacing = value(&arg, args.next())?,
+            "--cell-height" => options.cell_height = Some(value(&arg, args.next())?),
+            "--noodle" => {
+                options.noodle = match value::<String>(&arg, args.next())?.as_str() {
+                    "circle" => NoodleShape::Circle,
+                    "arc" => NoodleShape::Arc,
+                    "polygon" => NoodleShape::Polygon,
+                    path => NoodleShape::Vertices(Noodle::from_file(Path::new(path))?),
+                }
+            }
+            "--pi-free" => options.pi_free = true,
+            "--pixel-grid" => options.pixel_grid = Some(value(&arg, args.next())?),
+            "--points" => {
+                options.points = match value::<String>(&arg, args.next())?.as_str() {
+                    "random" => PointSource::Random,
+                    "halton" => PointSource::Halton,
+                    "sobol" => PointSource::Sobol,
+                    "hammersley" => PointSource::Hammersley { points: 0 },
+                    other => return Err(format!("unknown points '{}'", other)),
+                }
+            }
+            "--compare-points" => options.compare_points = true,
+            "--variance-reduction" => match value::<String>(&arg, args.next())?.as_str() {
+                "hit-or-miss" => options.variance_reduction = VarianceReduction::HitOrMiss,
+                "antithetic" => options.variance_reduction = VarianceReduction::Antithetic,
+                "stratified" => {
+                    options.variance_reduction = VarianceReduction::Stratified { strata: STRATA }
+                }
+                "latin-hypercube" => {
+                    options.variance_reduction = VarianceReduction::LatinHypercube {
+                        points: LATIN_HYPERCUBE_POINTS,
+                    }
+                }
+                "mean-value" => options.variance_reduction = VarianceReduction::MeanValue,
+                "control-variate" => options.variance_reduction = VarianceReduction::ControlVariate,
+                "crossings" => options.buffon_reduction = buffon::VarianceReduction::Crossings,
+                "rao-blackwell" => {
+                    options.buffon_reduction = buffon::VarianceReduction::RaoBlackwell
+                }
+                "importance-sampling" => {
+                    options.buffon_reduction = buffon::VarianceReduction::ImportanceSampling
+                }
+                other => return Err(format!("unknown variance reduction '{}'", other)),
+            },
+            "--compare-variance" => options.compare_variance = true,
+            "--seed" => options.seed = Some(value(&arg, args.next())?),
+            "-j" | "--threads" => options.threads = value(&arg, args.next())?,
+            "-f" | "--format" => {
+                options.format = match value::<String>(&arg, args.next())?.as_str() {
+                    "text" => Format::Text,
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    other => return Err(format!("unknown format '{}'", other)),
+                }
+            }
+            "--trace" => options.trace = Some(value(&arg, args.next())?),
+            "--trace-points" => options.trace_points = value(&arg, args.next())?,
+            "--render" => options.render = Some(value(&arg, args.next())?),
+            "--frames" => options.frames = value(&arg, args.next())?,
+            "--frame-step" => options.frame_step = value(&arg, args.next())?,
+            "--size" => options.size = value(&arg, args.next())?,
+            "--rate" => options.rate = Some(value(&arg, args.next())?),
+            "--hit-color" => options.hit_color = color(&arg, args.next())?,
+            "--miss-color" => options.miss_color = color(&arg, args.next())?,
+            "--no-gui" => options.gui = false,
+            "walk" | "buffon" | "laplace" | "noodle" | "circle" | "all" if method.is_none() => {
+                method = Some(match arg.as_str() {
+                    "walk" => Method::Walk,
+                    "buffon" => Method::Buffon,
+                    "laplace" => Method::Laplace,
+                    "noodle" => Method::Noodle,
+                    "circle" => Method::Circle,
+                    _ => Method::All,
+                });
+            }
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+    options.method = method.unwrap_or(Method::All);
+
+    if options.steps == 0 {
+        return Err("--steps must be at least 1".to_string());
+    }
+    if options.method.includes(Method::Walk) && options.steps == 1 && !options.asymptotic {
+        return Err(
+            "--steps must be at least 2, one step always ends 1 away and the exact formula would only give back pi; pass --asymptotic to walk one step"
+                .to_string(),
+        );
+    }
+    if options.method.includes(Method::Buffon) {
+        BuffonsNeedle::with_lengths(options.needle_length, options.line_spacing)?;
+    }
+    if options.method == Method::Laplace {
+        BuffonLaplace::with_lengths(
+            options.needle_length,
+            options.line_spacing,
+            options.cell_height(),
+        )?;
+    }
+    let positive_spacing = options.line_spacing > 0.0 && options.line_spacing.is_finite();
+    if options.method.includes(Method::Noodle) && !positive_spacing {
+        return Err("--line-spacing must be a positive number".to_string());
+    }
+    if options.pi_free && options.buffon_reduction == buffon::VarianceReduction::ImportanceSampling
+    {
+        return Err(
+            "--pi-free can't be used with importance-sampling, its angles need pi".to_string(),
+        );
+    }
+    let built_with_pi = matches!(options.noodle, NoodleShape::Circle | NoodleShape::Arc);
+    if options.pi_free && options.method.includes(Method::Noodle) && built_with_pi {
+        return Err(
+            "--pi-free can't be used with the circle or arc noodle, their shape is built with pi"
+                .to_string(),
+        );
+    }
+    if options.pixel_grid == Some(0) {
+        return Err("--pixel-grid must be at least 1".to_string());
+    }
+    if options.compare_points && options.method == Method::Walk {
+        return Err(
+            "--compare-points needs the buffon, laplace, noodle or circle method".to_string(),
+        );
+    }
+    let placed = options.variance_reduction.uses_points();
+    if options.method.includes(Method::Circle)
+        && !placed
+        && (options.points.is_quasi_random() || options.compare_points)
+    {
+        return Err(format!(
+            "{} darts land at pseudo-random points of their cells, so they can't be used with --points or --compare-points",
+            options.variance_reduction.name()
+        ));
+    }
+    let reducible =
+        options.method.includes(Method::Buffon) || options.method.includes(Method::Circle);
+    if options.compare_variance && !reducible {
+        return Err("--compare-variance needs the buffon or circle method".to_string());
+    }
+    if options.compare_variance && options.compare_points {
+        return Err("--compare-variance and --compare-points can't be used together".to_string());
+    }
+    if options.trace_points == 0 {
+        return Err("--trace-points must be at least 1".to_string());
+    }
+    if options.threads == 0 {
+        return Err("--threads must be at least 1".to_string());
+    }
+    Ok(Command::Run(Box::new(options)))
+}
+
+fn value<T>(flag: &str, value: Option<String>) -> Result<T, String>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|error| format!("invalid value '{}' for {}: {}", value, flag, error))
+}
+
+fn color(flag: &str, value: Option<String>) -> Result<Rgba<u8>, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    parse_color(&value).map_err(|error| format!("invalid value for {}: {}", flag, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &str) -> Result<Options, String> {
+        match parse(args.split_whitespace().map(String::from))? {
+            Command::Run(options) => Ok(*options),
+            Command::Help => Err("help".to_string()),
+        }
+    }
+
+    #[test]
+    fn no_arguments_run_every_method() {
+        let options = parse_args("").unwrap();
+        assert_eq!(options.method, Method::All);
+        assert_eq!(options.samples, None);
+        assert_eq!(options.steps, 100);
+        assert_eq!(options.format, Format::Text);
+        assert!(options.gui);
+    }
+
+    #[test]
+    fn method_and_flags_are_read() {
+        let options = parse_args("buffon -n 500 --seed 3 -j 2 -f csv --no-gui").unwrap();
+        assert_eq!(options.method, Method::Buffon);
+        assert_eq!(options.samples, Some(500));
+        assert_eq!(options.seed, Some(3));
+        assert_eq!(options.threads, 2);
+        assert_eq!(options.format, Format::Csv);
+        assert!(!options.gui);
+
+        let options =
+            parse_args("--variance-reduction stratified circle --variance-reduction rao-blackwell")
+                .unwrap();
+        assert_eq!(options.method, Method::Circle);
+        assert_eq!(
+            options.variance_reduction,
+            VarianceReduction::Stratified { strata: STRATA }
+        );
+        assert_eq!(
+            options.buffon_reduction,
+            buffon::VarianceReduction::RaoBlackwell
+        );
+    }
+
+    #[test]
+    fn help_wins() {
+        assert_eq!(parse_args("circle --help").err(), Some("help".to_string()));
+    }
+
+    #[test]
+    fn bad_arguments_are_reported() {
+        let errors = [
+            ("walk circle", "unexpected argument 'circle'"),
+            ("--frobnicate", "unexpected argument '--frobnicate'"),
+            ("-n", "-n needs a value"),
+            ("--format yaml", "unknown format 'yaml'"),
+            ("--points grid", "unknown points 'grid'"),
+            (
+                "--hit-color red",
+                "invalid value for --hit-color: 'red' is not a colour like ff8000",
+            ),
+            ("--steps 0", "--steps must be at least 1"),
+            ("-j 0", "--threads must be at least 1"),
+            (
+                "walk --compare-points",
+                "--compare-points needs the buffon, laplace, noodle or circle method",
+            ),
+            (
+                "--compare-variance --compare-points",
+                "--compare-variance and --compare-points can't be used together",
+            ),
+        ];
+        for (args, error) in errors {
+            assert_eq!(parse_args(args).err().as_deref(), Some(error), "{}", args);
+        }
+        let error = parse_args("-n many").unwrap_err();
+        assert!(
+            error.starts_with("invalid value 'many' for -n"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn conflicting_options_are_rejected() {
+        for args in [
+            "walk --steps 1",
+            "laplace --needle-length 2",
+            "buffon --needle-length 0",
+            "--pi-free --variance-reduction importance-sampling",
+            "noodle --pi-free --noodle circle",
+            "circle --points sobol --variance-reduction stratified",
+            "circle --compare-points --variance-reduction latin-hypercube",
+            "walk --compare-points",
+        ] {
+            assert!(parse_args(args).is_err(), "{}", args);
+        }
+    }
+
+    #[test]
+    fn every_method_skips_the_grid_for_long_needles() {
+        let options = parse_args("--needle-length 2").unwrap();
+        assert!(!options.runs_laplace());
+        assert!(options.laplace_skipped().is_some());
+        let options = parse_args("buffon --needle-length 2").unwrap();
+        assert!(options.laplace_skipped().is_none());
+        assert!(parse_args("walk --steps 1 --asymptotic").is_ok());
+        assert!(parse_args("buffon --pi-free --noodle arc").is_ok());
+    }
+}
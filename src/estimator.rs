@@ -0,0 +1,93 @@
+use std::fmt;
+
+use rand::RngCore;
+
+/// z value for a two sided 95% confidence interval of a normal distribution.
+pub const Z_95: f64 = 1.959_963_984_540_054;
+
+/// Common interface for every Monte Carlo method that approximates pi.
+///
+/// An estimator keeps running totals, so samples can be drawn in batches
+/// (e.g. one batch per frame) and the estimate read at any point.
+pub trait PiEstimator {
+    /// Human readable name of the method.
+    ///
+    /// It names every option that changes the samples, e.g. `buffons needle (pi-free, sobol)`.
+    fn name(&self) -> String;
+
+    /// Draw `n` more samples from `rng` and add them to the running totals.
+    ///
+    /// Using a seeded `rng` (see [`crate::rng::seeded_rng`]) makes the run reproducible.
+    fn sample(&mut self, rng: &mut dyn RngCore, n: u64);
+
+    /// Current approximation of pi, with its error, from the samples drawn so far.
+    fn estimate(&self) -> Estimate;
+
+    /// Number of samples drawn so far.
+    fn sample_count(&self) -> u64;
+
+    /// Forget every sample drawn so far, keeping the configuration.
+    fn reset(&mut self);
+
+    /// Make the next sample the one at position `index` of the run.
+    ///
+    /// Only matters for estimators whose samples depend on their position, like
+    /// those drawing from a quasi-random [`PointSource`](crate::points::PointSource).
+    /// [`ParallelSampler`](crate::parallel::ParallelSampler) calls it on the copy
+    /// that draws each block.
+    fn skip_to(&mut self, index: u64) {
+        let _ = index;
+    }
+
+    /// Add the running totals of `other`, an estimator with the same configuration.
+    fn merge(&mut self, other: &Self)
+    where
+        Self: Sized;
+}
+
+/// An approximation of pi together with how much it can be trusted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    /// The approximation of pi.
+    pub value: f64,
+    /// Number of samples it was computed from.
+    pub samples: u64,
+    /// Standard error of `value`.
+    pub std_error: f64,
+    /// 95% confidence interval `(low, high)` for pi.
+    pub confidence_interval: (f64, f64),
+}
+
+impl Estimate {
+    /// Build an estimate with a normal 95% confidence interval `value +- 1.96 * std_error`.
+    pub fn new(value: f64, samples: u64, std_error: f64) -> Self {
+        let half_width = Z_95 * std_error;
+        Estimate {
+            value,
+            samples,
+            std_error,
+            confidence_interval: (value - half_width, value + half_width),
+        }
+    }
+
+    /// Half the width of the 95% confidence interval.
+    pub fn margin(&self) -> f64 {
+        (self.confidence_interval.1 - self.confidence_interval.0) / 2_f64
+    }
+}
+
+/// `method` followed by the `qualifiers` that are set, in brackets.
+pub(crate) fn qualified_name(method: &str, qualifiers: &[Option<&str>]) -> String {
+    let qualifiers: Vec<&str> = qualifiers.iter().flatten().copied().collect();
+    if qualifiers.is_empty() {
+        method.to_string()
+    } else {
+        format!("{} ({})", method, qualifiers.join(", "))
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.6} ± {:.6}", self.value, self.margin())
+    }
+}
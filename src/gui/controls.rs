@@ -0,0 +1,62 @@
+// Keyboard and mouse controls of the visuals window
+use piston_window::{Button, Key, MouseButton};
+
+// How much faster or slower each press makes the sampling
+pub const RATE_FACTOR: f64 = 2.0;
+
+/// Something the user asked the window to do.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Control {
+    TogglePause,
+    Step,
+    Reset,
+    Faster,
+    Slower,
+    ToggleCaption,
+    TogglePlot,
+    ToggleHelp,
+}
+
+/// Key bindings, as shown in the help overlay.
+pub const HELP: [&str; 8] = [
+    "Space / left click: pause or resume",
+    "S / right click: draw one frame of samples",
+    "R: reset the counts and the canvas",
+    "Up / + / scroll up: sample faster",
+    "Down / - / scroll down: sample slower",
+    "C: show or hide the estimate",
+    "P: show or hide the convergence plot",
+    "H: show or hide this help, Esc: close",
+];
+
+/// The control bound to a key or mouse button, if any.
+pub fn control(button: Button) -> Option<Control> {
+    let control = match button {
+        Button::Keyboard(Key::Space) | Button::Mouse(MouseButton::Left) => Control::TogglePause,
+        Button::Keyboard(Key::S) | Button::Mouse(MouseButton::Right) => Control::Step,
+        Button::Keyboard(Key::R) => Control::Reset,
+        Button::Keyboard(Key::Up)
+        | Button::Keyboard(Key::Plus)
+        | Button::Keyboard(Key::Equals)
+        | Button::Keyboard(Key::NumPadPlus) => Control::Faster,
+        Button::Keyboard(Key::Down)
+        | Button::Keyboard(Key::Minus)
+        | Button::Keyboard(Key::NumPadMinus) => Control::Slower,
+        Button::Keyboard(Key::C) => Control::ToggleCaption,
+        Button::Keyboard(Key::P) => Control::TogglePlot,
+        Button::Keyboard(Key::H) => Control::ToggleHelp,
+        _ => return None,
+    };
+    Some(control)
+}
+
+/// The control for turning the mouse wheel by `[x, y]`, if any.
+pub fn scroll_control(scroll: [f64; 2]) -> Option<Control> {
+    if scroll[1] > 0.0 {
+        Some(Control::Faster)
+    } else if scroll[1] < 0.0 {
+        Some(Control::Slower)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,194 @@
+// Piston engine for the visuals of each method
+use piston_window::*;
+// Need image to save previous frame and loaded onto next frame
+use ::image;
+// For loading font to display digits of pi
+use ::find_folder;
+
+use std::time::Instant;
+
+use rand::RngCore;
+
+use approximating_pi::render::{pace, Layout, Pacer, Rate, Scene, BACKGROUND_COLOR, TEXT_COLOR};
+
+mod controls;
+use controls::{Control, HELP, RATE_FACTOR};
+mod plot;
+use plot::{ConvergencePlot, PLOT_WIDTH};
+
+// Size and spacing of the help overlay's text
+const HELP_TEXT_SIZE: u32 = 16;
+const HELP_LINE_HEIGHT: f64 = 22.0;
+const HELP_BACKGROUND_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.85];
+
+/// Open a window that plays `scene` at `rate`, advancing it one frame per frame drawn.
+///
+/// The scene is rendered by the same code that writes PNG frames and GIFs,
+/// only the caption, help and convergence plot are drawn by piston. The plot
+/// sits on the right of the canvas, which starts at the scene's size and is
+/// laid out again whenever the window is resized.
+/// The keys and mouse buttons in `controls` pause, step, reset and speed it up.
+pub fn show(scene: &mut dyn Scene, rng: &mut dyn RngCore, rate: Rate) {
+    let layout = scene.layout();
+    let mut window_size = [layout.width + PLOT_WIDTH, layout.height];
+    let mut window: PistonWindow = WindowSettings::new(scene.title(), window_size)
+        .exit_on_esc(true)
+        .resizable(true)
+        .build()
+        .unwrap();
+
+    // Create an image buffer to render each frame into before it's loaded onto the texture
+    let mut frame = image::RgbaImage::from_pixel(layout.width, layout.height, BACKGROUND_COLOR);
+    let mut texture_context = TextureContext {
+        factory: window.factory.clone(),
+        encoder: window.factory.create_command_buffer().into(),
+    };
+    let mut texture: G2dTexture =
+        Texture::from_image(&mut texture_context, &frame, &TextureSettings::new()).unwrap();
+
+    // Set up font for text to show pi
+    let assets = find_folder::Search::ParentsThenKids(3, 3)
+        .for_folder("assets")
+        .unwrap();
+    println!("{:?}", assets);
+    let mut glyphs = window
+        .load_font(assets.join("FiraSans-Regular.ttf"))
+        .unwrap();
+    let text_color = [
+        TEXT_COLOR[0] as f32 / 255.0,
+        TEXT_COLOR[1] as f32 / 255.0,
+        TEXT_COLOR[2] as f32 / 255.0,
+        1.0,
+    ];
+
+    let mut pacer = Pacer::new(rate);
+    let mut last_frame = Instant::now();
+    let mut paused = false;
+    // Frames to draw while paused, one per press of the step control
+    let mut steps = 0;
+    let mut show_caption = true;
+    let mut show_plot = true;
+    let mut show_help = false;
+    let mut plot = ConvergencePlot::new();
+
+    println!(
+        "Displaying visuals for {}... (press H for the controls)",
+        scene.title()
+    );
+    while let Some(e) = window.next() {
+        let pressed = e.press_args().and_then(controls::control);
+        let scrolled = e.mouse_scroll_args().and_then(controls::scroll_control);
+        if let Some(control) = pressed.or(scrolled) {
+            match control {
+                Control::TogglePause => paused = !paused,
+                Control::Step => {
+                    paused = true;
+                    steps += 1;
+                }
+                Control::Reset => {
+                    scene.reset();
+                    plot.reset();
+                }
+                Control::Faster => pacer.set_rate(pacer.rate().scaled(RATE_FACTOR)),
+                Control::Slower => pacer.set_rate(pacer.rate().scaled(1.0 / RATE_FACTOR)),
+                Control::ToggleCaption => show_caption = !show_caption,
+                Control::TogglePlot => show_plot = !show_plot,
+                Control::ToggleHelp => show_help = !show_help,
+            }
+        }
+
+        if let Some(args) = e.resize_args() {
+            let [width, height] = args.window_size;
+            window_size = [width as u32, height as u32];
+        }
+
+        // The canvas gets whatever the plot leaves of the window
+        let plot_width = if show_plot { PLOT_WIDTH } else { 0 };
+        let layout = Layout::new(window_size[0].saturating_sub(plot_width), window_size[1]);
+        if layout != scene.layout() {
+            scene.resize(layout);
+            // The frame and texture have to match the new size
+            frame = image::RgbaImage::from_pixel(layout.width, layout.height, BACKGROUND_COLOR);
+            texture =
+                Texture::from_image(&mut texture_context, &frame, &TextureSettings::new()).unwrap();
+        }
+
+        window.draw_2d(&e, |c, g, device| {
+            // Clear display to white
+            clear([1.0; 4], g);
+
+            // Draw every sample due since the last frame in one go
+            let now = Instant::now();
+            let seconds = now.duration_since(last_frame).as_secs_f64();
+            last_frame = now;
+            if !paused {
+                scene.advance(rng, pacer.samples(seconds));
+            } else if steps > 0 {
+                // A step always draws something, even when the rate is below a sample a frame
+                scene.advance(rng, pacer.samples(pace::FRAME_SECONDS).max(1));
+                steps -= 1;
+            }
+            plot.record(scene.estimate());
+
+            // Update texture, once per frame however many samples were drawn
+            for pixel in frame.pixels_mut() {
+                *pixel = BACKGROUND_COLOR;
+            }
+            scene.render(&mut frame);
+            texture.update(&mut texture_context, &frame).unwrap();
+            image(&texture, c.transform, g);
+            texture_context.encoder.flush(device);
+
+            // Draw text for pi approximation
+            if show_caption {
+                let [x, y] = scene.layout().caption_position();
+                let transform = c.transform.trans(x, y);
+
+                text::Text::new_color(text_color, scene.caption_size())
+                    .draw(&scene.caption(), &mut glyphs, &c.draw_state, transform, g)
+                    .unwrap();
+            }
+
+            // Draw the convergence plot on the right of the canvas
+            if show_plot {
+                let layout = scene.layout();
+                let area = [
+                    layout.width as f64,
+                    0.0,
+                    PLOT_WIDTH as f64,
+                    layout.canvas_height() as f64,
+                ];
+                plot.draw(area, &mut glyphs, &c, g);
+            }
+
+            // Draw the key bindings over the canvas
+            if show_help {
+                let status = format!(
+                    "{} at {}",
+                    if paused { "Paused" } else { "Running" },
+                    pacer.rate()
+                );
+                let lines: Vec<&str> = HELP.iter().copied().chain(Some(status.as_str())).collect();
+                let width = scene.layout().canvas_width() as f64 - 20.0;
+                let height = HELP_LINE_HEIGHT * lines.len() as f64 + 10.0;
+                rectangle(
+                    HELP_BACKGROUND_COLOR,
+                    [10.0, 10.0, width, height],
+                    c.transform,
+                    g,
+                );
+                for (i, line) in lines.iter().enumerate() {
+                    let transform = c
+                        .transform
+                        .trans(20.0, 10.0 + HELP_LINE_HEIGHT * (i + 1) as f64);
+                    text::Text::new_color(text_color, HELP_TEXT_SIZE)
+                        .draw(line, &mut glyphs, &c.draw_state, transform, g)
+                        .unwrap();
+                }
+            }
+
+            // Update glyphs before rendering.
+            glyphs.factory.encoder.flush(device);
+        });
+    }
+}
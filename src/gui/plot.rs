@@ -0,0 +1,166 @@
+// Live plot of how the estimate converges, drawn next to the canvas
+use piston_window::*;
+
+use std::f64::consts::PI;
+
+use approximating_pi::estimator::Estimate;
+use approximating_pi::trace::TracePoint;
+
+// Width of the panel on the right of the canvas
+pub const PLOT_WIDTH: u32 = 300;
+// Points recorded per power of ten of samples
+const POINTS_PER_DECADE: f64 = 20.0;
+// Space around the plot for the axis labels
+const MARGIN_LEFT: f64 = 50.0;
+const MARGIN: f64 = 20.0;
+const LABEL_SIZE: u32 = 12;
+
+const AXIS_COLOR: [f32; 4] = [0.6, 0.6, 0.6, 1.0];
+const PI_COLOR: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+const ESTIMATE_COLOR: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+const BAND_COLOR: [f32; 4] = [0.0, 0.0, 1.0, 0.2];
+const LABEL_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+/// The estimate against log(n), with its 95% confidence band and a line at pi.
+///
+/// Points are recorded at log spaced sample counts, like a [`Trace`](approximating_pi::Trace),
+/// so every decade takes the same width however long the window runs.
+#[derive(Default)]
+pub struct ConvergencePlot {
+    points: Vec<TracePoint>,
+    // Next power of 10^(1 / POINTS_PER_DECADE) to record a point at
+    next_checkpoint: u32,
+    latest: Option<TracePoint>,
+}
+
+impl ConvergencePlot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the estimate after the latest frame, keeping it if it passed a checkpoint.
+    pub fn record(&mut self, estimate: Estimate) {
+        if estimate.samples == 0 || !estimate.value.is_finite() || !estimate.std_error.is_finite() {
+            return;
+        }
+        let point = TracePoint {
+            samples: estimate.samples,
+            estimate: estimate.value,
+            std_error: estimate.std_error,
+        };
+        if estimate.samples as f64 >= checkpoint(self.next_checkpoint) {
+            self.points.push(point);
+            while estimate.samples as f64 >= checkpoint(self.next_checkpoint) {
+                self.next_checkpoint += 1;
+            }
+        }
+        self.latest = Some(point);
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Draw the plot into `area`, `[x, y, width, height]` in window coordinates.
+    pub fn draw(&self, area: [f64; 4], glyphs: &mut Glyphs, c: &Context, g: &mut G2d) {
+        let [x, y, width, height] = area;
+        let plot = [
+            x + MARGIN_LEFT,
+            y + MARGIN,
+            width - MARGIN_LEFT - MARGIN,
+            height - 2.0 * MARGIN,
+        ];
+        let [left, top, plot_width, plot_height] = plot;
+        if plot_width <= 0.0 || plot_height <= 0.0 {
+            return;
+        }
+        let (right, bottom) = (left + plot_width, top + plot_height);
+
+        let points: Vec<TracePoint> = self.points.iter().copied().chain(self.latest).collect();
+        // At least one decade across, growing a decade at a time
+        let decades = points
+            .last()
+            .map_or(1.0, |point| (point.samples as f64).log10().ceil())
+            .max(1.0);
+        // Wide enough for the estimates and their bands once past the first few samples
+        let spread = points
+            .iter()
+            .filter(|point| point.samples >= 10)
+            .map(|point| (point.estimate - PI).abs() + 2.0 * point.std_error)
+            .fold(0.01_f64, f64::max)
+            .min(1.0);
+        let to_x = |samples: u64| left + plot_width * (samples as f64).log10() / decades;
+        let to_y = |value: f64| {
+            let value = value.max(PI - spread).min(PI + spread);
+            top + plot_height / 2.0 * (1.0 - (value - PI) / spread)
+        };
+
+        // Shaded 95% confidence band
+        for pair in points.windows(2) {
+            let (from, to) = (band(&pair[0]), band(&pair[1]));
+            let (x0, x1) = (to_x(pair[0].samples), to_x(pair[1].samples));
+            let quad = [
+                [x0, to_y(from.0)],
+                [x1, to_y(to.0)],
+                [x1, to_y(to.1)],
+                [x0, to_y(from.1)],
+            ];
+            polygon(BAND_COLOR, &quad, c.transform, g);
+        }
+
+        // Axes and the true value of pi
+        line_from_to(AXIS_COLOR, 0.5, [left, top], [left, bottom], c.transform, g);
+        line_from_to(
+            AXIS_COLOR,
+            0.5,
+            [left, bottom],
+            [right, bottom],
+            c.transform,
+            g,
+        );
+        line_from_to(
+            PI_COLOR,
+            0.5,
+            [left, to_y(PI)],
+            [right, to_y(PI)],
+            c.transform,
+            g,
+        );
+
+        for pair in points.windows(2) {
+            let from = [to_x(pair[0].samples), to_y(pair[0].estimate)];
+            let to = [to_x(pair[1].samples), to_y(pair[1].estimate)];
+            line_from_to(ESTIMATE_COLOR, 0.75, from, to, c.transform, g);
+        }
+
+        // n on a log scale along the bottom, the estimate up the side
+        let mut label = |text: &str, position: [f64; 2], glyphs: &mut Glyphs| {
+            let transform = c.transform.trans(position[0], position[1]);
+            text::Text::new_color(LABEL_COLOR, LABEL_SIZE)
+                .draw(text, glyphs, &c.draw_state, transform, g)
+                .unwrap();
+        };
+        for decade in 0..=decades as u32 {
+            let x = left + plot_width * decade as f64 / decades;
+            label(&format!("1e{}", decade), [x - 8.0, bottom + 14.0], glyphs);
+        }
+        label("pi", [x + 8.0, to_y(PI) + 4.0], glyphs);
+        label(&format!("{:.3}", PI + spread), [x + 8.0, top + 4.0], glyphs);
+        label(
+            &format!("{:.3}", PI - spread),
+            [x + 8.0, bottom + 4.0],
+            glyphs,
+        );
+    }
+}
+
+// Sample count of checkpoint `k`
+fn checkpoint(k: u32) -> f64 {
+    10_f64.powf(k as f64 / POINTS_PER_DECADE)
+}
+
+// The 95% confidence interval around a point
+fn band(point: &TracePoint) -> (f64, f64) {
+    let margin = approximating_pi::estimator::Z_95 * point.std_error;
+    (point.estimate - margin, point.estimate + margin)
+}
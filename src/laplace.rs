@@ -0,0 +1,278 @@
+use rand::{Rng, RngCore};
+
+use crate::buffon::{disk_direction, Direction};
+use crate::estimator::{qualified_name, Estimate, PiEstimator};
+use crate::map;
+use crate::points::{PointCursor, PointSource};
+
+// Buffon-Laplace: the lines form a grid of a x b rectangles. A needle of length
+// l <= min(a, b) crosses at least one line with probability
+// p = (2l(a + b) - l^2) / (pi a b), so pi ~ (2l(a + b) - l^2) / (a b x / n)
+// after x of n needles came to rest crossing a line. Needles cross more often
+// than on parallel lines, so the same number of drops gives a smaller error.
+
+/// Where a needle came to rest in its cell.
+///
+/// `start_x` and `end_x` are measured from the vertical line to the left of the
+/// start, `start_y` and `end_y` from the horizontal line below it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridNeedle {
+    pub start_x: f64,
+    pub start_y: f64,
+    pub end_x: f64,
+    pub end_y: f64,
+    pub crosses: bool,
+}
+
+/// The Buffon-Laplace needle: drop needles on a grid of rectangles and count
+/// how many cross a line of either family.
+///
+/// One sample is one needle drop. The position and angle of each needle are
+/// pseudo-random unless another [`PointSource`] is chosen with
+/// [`BuffonLaplace::with_points`], and they are turned by an angle unless
+/// another [`Direction`] is chosen with [`BuffonLaplace::with_direction`].
+#[derive(Debug, Clone)]
+pub struct BuffonLaplace {
+    needle_length: f64,
+    cell_width: f64,
+    cell_height: f64,
+    points: PointCursor,
+    direction: Direction,
+    drops: u64,
+    crossings: u64,
+}
+
+impl BuffonLaplace {
+    /// Needles as long as the sides of the square cells.
+    pub fn new() -> Self {
+        BuffonLaplace {
+            needle_length: 1_f64,
+            cell_width: 1_f64,
+            cell_height: 1_f64,
+            points: PointCursor::default(),
+            direction: Direction::Angle,
+            drops: 0,
+            crossings: 0,
+        }
+    }
+
+    /// Needles `needle_length` long on a grid of `cell_width` x `cell_height` rectangles.
+    ///
+    /// Both sides must be positive and the needle no longer than the shorter
+    /// one, nor shorter than [`MIN_LENGTH_RATIO`](crate::buffon::MIN_LENGTH_RATIO) times it.
+    pub fn with_lengths(
+        needle_length: f64,
+        cell_width: f64,
+        cell_height: f64,
+    ) -> Result<Self, String> {
+        if !(needle_length > 0_f64 && needle_length.is_finite()) {
+            return Err(format!(
+                "the needle length must be a positive number, not {}",
+                needle_length
+            ));
+        }
+        for &(side, length) in &[("width", cell_width), ("height", cell_height)] {
+            if !(length > 0_f64 && length.is_finite()) {
+                return Err(format!(
+                    "the grid cell {} must be a positive number, not {}",
+                    side, length
+                ));
+            }
+        }
+        let ratio = needle_length / cell_width.min(cell_height);
+        if !(crate::buffon::MIN_LENGTH_RATIO..=1_f64).contains(&ratio) {
+            return Err(format!(
+                "the needle must be between {} and 1 times as long as the shorter side of the grid cells, not {}",
+                crate::buffon::MIN_LENGTH_RATIO,
+                ratio
+            ));
+        }
+        Ok(BuffonLaplace {
+            needle_length,
+            cell_width,
+            cell_height,
+            ..Self::new()
+        })
+    }
+
+    /// Drop the needles at the points of `points` instead of pseudo-random ones.
+    pub fn with_points(mut self, points: PointSource) -> Self {
+        self.points = PointCursor::new(points);
+        self
+    }
+
+    /// Turn the needles with `direction`, e.g. without using pi.
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn points(&self) -> PointSource {
+        self.points.source()
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Number of needles that came to rest crossing a line of either family.
+    pub fn crossings(&self) -> u64 {
+        self.crossings
+    }
+
+    pub fn needle_length(&self) -> f64 {
+        self.needle_length
+    }
+
+    pub fn cell_width(&self) -> f64 {
+        self.cell_width
+    }
+
+    pub fn cell_height(&self) -> f64 {
+        self.cell_height
+    }
+
+    /// Drop `n` more needles like [`PiEstimator::sample`], calling `observe` with each one.
+    pub fn sample_with<F>(&mut self, rng: &mut dyn RngCore, n: u64, mut observe: F)
+    where
+        F: FnMut(GridNeedle),
+    {
+        let two_pi = std::f64::consts::TAU;
+
+        for _ in 0..n {
+            let [u, v, w] = match self.direction {
+                Direction::Angle => self.points.point(rng),
+                // The direction doesn't come from the points
+                Direction::Disk => {
+                    let [u, v] = self.points.point(rng);
+                    [u, v, 0_f64]
+                }
+            };
+            self.points.advance();
+            let start_x = map(u, 0_f64, 1_f64, 0_f64, self.cell_width);
+            let start_y = map(v, 0_f64, 1_f64, 0_f64, self.cell_height);
+
+            let (cos, sin) = match self.direction {
+                Direction::Angle => {
+                    let angle = map(w, 0_f64, 1_f64, 0_f64, two_pi);
+                    (f64::cos(angle), f64::sin(angle))
+                }
+                Direction::Disk => disk_direction(rng),
+            };
+            let end_x = start_x + self.needle_length * cos;
+            let end_y = start_y + self.needle_length * sin;
+
+            // A needle ending outside its cell has crossed a vertical or a horizontal line
+            let crosses = end_x < 0_f64
+                || end_x > self.cell_width
+                || end_y < 0_f64
+                || end_y > self.cell_height;
+            if crosses {
+                self.crossings += 1;
+            }
+            observe(GridNeedle {
+                start_x,
+                start_y,
+                end_x,
+                end_y,
+                crosses,
+            });
+        }
+        self.drops += n;
+    }
+}
+
+impl Default for BuffonLaplace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PiEstimator for BuffonLaplace {
+    fn name(&self) -> String {
+        let pi_free = (self.direction == Direction::Disk).then_some("pi-free");
+        let points = self.points.source().qualifier();
+        qualified_name("buffon-laplace grid", &[pi_free, points])
+    }
+
+    fn sample(&mut self, rng: &mut dyn RngCore, n: u64) {
+        self.sample_with(rng, n, |_| {});
+    }
+
+    fn estimate(&self) -> Estimate {
+        let n = self.drops as f64;
+        let (l, a, b) = (self.needle_length, self.cell_width, self.cell_height);
+        let p = self.crossings as f64 / n;
+        let value = (2_f64 * l * (a + b) - l * l) / (a * b * p);
+
+        // Delta method, as for Buffon's needle: se(pi) = pi * sqrt((1 - p) / (n * p))
+        let std_error = value * ((1_f64 - p) / (n * p)).sqrt();
+
+        Estimate::new(value, self.drops, std_error)
+    }
+
+    fn sample_count(&self) -> u64 {
+        self.drops
+    }
+
+    fn reset(&mut self) {
+        self.points.skip_to(0);
+        self.drops = 0;
+        self.crossings = 0;
+    }
+
+    fn skip_to(&mut self, index: u64) {
+        self.points.skip_to(index);
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.points.merge(&other.points);
+        self.drops += other.drops;
+        self.crossings += other.crossings;
+    }
+}
+
+/// Approximate pi by dropping `iterations` needles on a grid of unit squares, drawing from `rng`.
+pub fn buffon_laplace<R: Rng>(rng: &mut R, iterations: u64) -> Estimate {
+    let mut estimator = BuffonLaplace::new();
+    estimator.sample(rng, iterations);
+    estimator.estimate()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::seeded_rng;
+
+    #[test]
+    fn seeded_estimate_is_exact() {
+        let estimate = buffon_laplace(&mut seeded_rng(1), 10_000);
+        assert_eq!(estimate.value, 3.1393888656341566);
+        assert_eq!(estimate.samples, 10_000);
+    }
+
+    #[test]
+    fn lengths_are_checked() {
+        // Longer than the shorter side
+        assert!(BuffonLaplace::with_lengths(1.5, 2.0, 1.0).is_err());
+        assert!(BuffonLaplace::with_lengths(0.0, 1.0, 1.0).is_err());
+        assert!(BuffonLaplace::with_lengths(1.0, 0.0, 1.0).is_err());
+        assert!(BuffonLaplace::with_lengths(f64::NAN, 1.0, 1.0).is_err());
+        assert!(BuffonLaplace::with_lengths(1.0, 1.0, f64::NAN).is_err());
+        assert!(BuffonLaplace::with_lengths(1e-4, 1.0, 1.0).is_err());
+        assert!(BuffonLaplace::with_lengths(1.0, 2.0, 1.0).is_ok());
+    }
+
+    #[test]
+    fn rectangular_cells_estimate_pi() {
+        for direction in [Direction::Angle, Direction::Disk] {
+            let mut estimator = BuffonLaplace::with_lengths(1.0, 2.0, 1.5)
+                .unwrap()
+                .with_direction(direction);
+            estimator.sample(&mut seeded_rng(1), 100_000);
+            let estimate = estimator.estimate();
+            let error = (estimate.value - std::f64::consts::PI).abs();
+            assert!(error < 4.0 * estimate.std_error, "{}", estimate);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Approximating pi using Monte Carlo methods
+//!
+//! The estimators live here so they can be used without the piston visuals.
+//! The binary in `main.rs` is a thin consumer of this library.
+
+pub mod buffon;
+pub mod circle;
+pub mod estimator;
+pub mod laplace;
+pub mod noodle;
+pub mod parallel;
+pub mod points;
+pub mod random_walk;
+pub mod render;
+pub mod rng;
+pub mod trace;
+
+pub use buffon::{buffons_needle, BuffonsNeedle, Needle};
+pub use circle::{circle_inside_square, CircleInSquare, Dart, Sampling, VarianceReduction};
+pub use estimator::{Estimate, PiEstimator};
+pub use laplace::{buffon_laplace, BuffonLaplace, GridNeedle};
+pub use noodle::{BuffonsNoodle, Noodle};
+pub use parallel::ParallelSampler;
+pub use points::PointSource;
+pub use random_walk::{random_walk, RandomWalk};
+pub use rng::{seeded_rng, SeededRng};
+pub use trace::Trace;
+
+/// Map `val` from the range `[min, max]` to the range `[new_min, new_max]`.
+pub fn map(val: f64, min: f64, max: f64, new_min: f64, new_max: f64) -> f64 {
+    // Map val to new val based on new range
+    let range = max - min;
+    let new_range = new_max - new_min;
+    ((val - min) / range) * new_range + new_min
+}
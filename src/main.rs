@@ -1,194 +1,713 @@
 // Approximating pi using Monte Carlo methods
 
-// The Rng trait defines methods that random number generates implement
-use rand::Rng;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use approximating_pi::buffon::{self, Direction};
+use approximating_pi::circle::{pixel_grid_bias, VarianceReduction};
+use approximating_pi::noodle::Noodle;
+use approximating_pi::points::PointSource;
+use approximating_pi::random_walk::{asymptotic_bias, Formula};
+use approximating_pi::render::{
+    self, CircleScene, GridScene, NeedleScene, NoodleScene, Scene, WalkScene,
+};
+use approximating_pi::rng;
+use approximating_pi::trace::{self, csv_field, json_number, Trace, TracePoint};
+use approximating_pi::{
+    BuffonLaplace, BuffonsNeedle, BuffonsNoodle, CircleInSquare, ParallelSampler, PiEstimator,
+    RandomWalk,
+};
+
+// How long each frame of a rendered GIF is shown
+const GIF_FRAME_DELAY_MS: u32 = 40;
+// Pieces of the circle and arc noodles, and sides of the random polygon
+const NOODLE_SEGMENTS: u32 = 64;
+const POLYGON_SIDES: u32 = 8;
+
+mod cli;
+use cli::{Command, Format, Method, NoodleShape, Options, LATIN_HYPERCUBE_POINTS, STRATA};
 
 // Piston engine for points inside circle approximaiton
-use piston_window::*;
-// Need image to save previous frame and loaded onto next frame
-use ::image;
-// For loading font to display digits of pi
-use ::find_folder;
+#[cfg(feature = "gui")]
+mod gui;
 
 fn main() {
-    let steps: u64 = 100;
-    let walks: u64 = 10_000;
-    println!("random walk: pi = {}", random_walk(steps, walks));
-    
-    let total_iterations: u64 = 1_000_000;
-    println!("buffons needle: pi = {}", buffons_needle(total_iterations));
-
-    // This one has visuals using piston_window library
-    // pi approximation is printed on the console
-    circle_inside_square();
-}
-
-fn circle_inside_square() {
-    // Display circle inside square pi approximation
-    const WIDTH: u32 = 512;
-    const HEIGHT: u32 = 540;
-    const TEXT_HEIGHT: u32 = 28;
-
-    let mut window: PistonWindow = WindowSettings::new("Approximating Pi", [WIDTH, HEIGHT])
-            .exit_on_esc(true).build().unwrap();
-
-    const GREEN: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
-
-    // Create an image buffer to save previous frame and draw it again on next frame
-    let mut canvas = image::ImageBuffer::new(WIDTH, HEIGHT);
-    let mut texture_context = TextureContext {
-        factory: window.factory.clone(),
-        encoder: window.factory.create_command_buffer().into()
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::Help) => {
+            print!("{}", cli::USAGE);
+            return;
+        }
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, cli::USAGE);
+            std::process::exit(2);
+        }
     };
-    let mut texture: G2dTexture = Texture::from_image(
-        &mut texture_context,
-        &canvas,
-        &TextureSettings::new()
-    ).unwrap();
-
-    // Set up font for text to show pi
-    let assets = find_folder::Search::ParentsThenKids(3, 3)
-    .for_folder("assets").unwrap();
-    println!("{:?}", assets);
-    let mut glyphs = window.load_font(assets.join("FiraSans-Regular.ttf")).unwrap();
-
-    // Monte carlo method for random points inside a circle:
-    // 1. Have a circle enclosed by a square with sides equal to the diameter of the circle
-    // 2. Generate a random set of points on the square
-    // 3. Area of the circle is pi * r^2 with r = 0.5
-    // 4. Area of square = 1 x 1
-    // 5. Divide area of circle by square we get pi / 4
-    // 6. pi / 4 ~ Ncircle / Ntotal
-    // 7. pi ~ 4 * Ncircle / Ntotal
-
-    // Counter for number of points in circle and total counter
-    let mut inside_counter = 0_f64;
-    let mut total_counter = 0_f64;
-    
-    println!("Displaying visuals for random points inside circle...");
-    let mut rng = rand::thread_rng();
-    while let Some(e) = window.next() {
-        window.draw_2d(&e, |c, g, device| {
-            // Clear display to white
-            clear([1.0; 4], g);
-            
-            let rect = [0.0, 0.0, WIDTH as f64, (HEIGHT-TEXT_HEIGHT) as f64];
-            ellipse(GREEN, rect, c.transform, g);
-            
-            
-            let pos_x = rng.gen_range(0, WIDTH);
-            let pos_y = rng.gen_range(0, HEIGHT-TEXT_HEIGHT);
-            
-            // Put generated square pixels into canvas
-            for i in 0..5 {
-                if pos_x + i < WIDTH {
-                    for j in 0..5 {
-                        if pos_y + j < HEIGHT-TEXT_HEIGHT {
-                            canvas.put_pixel(pos_x + i, pos_y + j, image::Rgba([255, 0, 0, 255]));
-                        }
-                    }
-                }
+    run(&options);
+}
+
+fn run(options: &Options) {
+    // Replay a previous run with --seed <u64>, otherwise pick a fresh seed
+    let seed = options.seed.unwrap_or_else(rand::random);
+    // Every method gets its own sampler, so `buffon --seed 5` replays the buffon row of `all --seed 5`
+    let sampler = ParallelSampler::new(seed, options.threads);
+
+    if options.compare_points {
+        let traces = compare_points(options, seed, &sampler);
+        write_traces(options, &traces);
+        return;
+    }
+    if options.compare_variance {
+        let traces = compare_variance(options, seed, &sampler);
+        write_traces(options, &traces);
+        return;
+    }
+
+    let mut estimators: Vec<Box<dyn PiEstimator>> = Vec::new();
+    let mut traces: Vec<Trace> = Vec::new();
+    if options.method.includes(Method::Walk) {
+        let walks = options.samples.unwrap_or(10_000);
+        estimators.push(Box::new(run_estimator(
+            sampler.clone(),
+            options,
+            &mut traces,
+            RandomWalk::with_formula(options.steps, walk_formula(options)),
+            walks,
+        )));
+    }
+    // Long needles can also be estimated from the needles crossing at least one line
+    let mut long_needles = None;
+    if options.method.includes(Method::Buffon) {
+        let total_iterations = options.samples.unwrap_or(1_000_000);
+        let needles = run_estimator(
+            sampler.clone(),
+            options,
+            &mut traces,
+            buffon_estimator(options, point_source(options, total_iterations)),
+            total_iterations,
+        );
+        // Importance sampled angles are not uniform, so the share of needles crossing means nothing
+        let uniform_angles =
+            options.buffon_reduction != buffon::VarianceReduction::ImportanceSampling;
+        if options.needle_length > options.line_spacing && uniform_angles {
+            long_needles = Some(needles.clone());
+        }
+        estimators.push(Box::new(needles));
+    }
+    if options.runs_laplace() {
+        let needles = options.samples.unwrap_or(1_000_000);
+        estimators.push(Box::new(run_estimator(
+            sampler.clone(),
+            options,
+            &mut traces,
+            laplace_estimator(options, point_source(options, needles)),
+            needles,
+        )));
+    }
+    if options.method.includes(Method::Noodle) {
+        let noodles = options.samples.unwrap_or(1_000_000);
+        estimators.push(Box::new(run_estimator(
+            sampler.clone(),
+            options,
+            &mut traces,
+            noodle_estimator(options, seed, point_source(options, noodles)),
+            noodles,
+        )));
+    }
+    if options.method.includes(Method::Circle) {
+        let darts = options.samples.unwrap_or(1_000_000);
+        estimators.push(Box::new(run_estimator(
+            sampler.clone(),
+            options,
+            &mut traces,
+            circle_estimator(options, point_source(options, darts)),
+            darts,
+        )));
+    }
+
+    print_results(options.format, seed, &estimators);
+
+    if options.format == Format::Text && options.method.includes(Method::Walk) {
+        println!(
+            "note: for {} steps the asymptotic random walk formula is biased by {:+.6}{}",
+            options.steps,
+            asymptotic_bias(options.steps),
+            if options.asymptotic {
+                ""
+            } else {
+                ", the exact formula corrects it"
             }
+        );
+    }
+    note_skipped(options);
+    let quasi_random = options.method != Method::Walk && options.points.is_quasi_random();
+    if options.format == Format::Text && quasi_random {
+        println!(
+            "note: the standard errors assume independent samples, so they overstate the error of {} points",
+            options.points.name()
+        );
+    }
+    if let (Some(needles), Format::Text) = (&long_needles, options.format) {
+        println!(
+            "note: {} of {} long needles crossed at least one line, which gives pi = {}",
+            needles.crossed_needles(),
+            needles.sample_count(),
+            needles.crossed_needle_estimate()
+        );
+    }
+    if let (Some(size), Format::Text) = (options.pixel_grid, options.format) {
+        if options.method.includes(Method::Circle) {
+            println!(
+                "note: darts on a {0}x{0} pixel grid are biased by {1:+.6} however many are thrown",
+                size,
+                pixel_grid_bias(size, size)
+            );
+        }
+    }
+
+    write_traces(options, &traces);
+
+    // The visuals are either saved as images or shown using the piston_window library
+    if let Some(path) = &options.render {
+        render_visuals(options, seed, path);
+    } else if options.gui {
+        #[cfg(feature = "gui")]
+        show_visuals(options, seed);
+    }
+}
+
+// Say which methods `all` left out and why
+fn note_skipped(options: &Options) {
+    if let (Some(reason), Format::Text) = (options.laplace_skipped(), options.format) {
+        println!("note: the buffon-laplace grid was skipped, {}", reason);
+    }
+}
 
-            // Map pos_x and pos_y between -1 and 1 (circle of radius 1 with center [0, 0])
-            let point_x = map(pos_x as f64, 0.0, WIDTH as f64, -1.0, 1.0);
-            let point_y = map(pos_y as f64, 0.0, (HEIGHT-TEXT_HEIGHT) as f64, -1.0, 1.0);
-            
-            // If Euclidean distance is less than the radius than it's inside the circle
-            let square_of_radius = 1; // square of 1 is 1
-            if point_x.powf(2_f64) + point_y.powf(2_f64) < square_of_radius as f64 {
-                inside_counter += 1_f64;
+fn write_traces(options: &Options, traces: &[Trace]) {
+    if let Some(path) = &options.trace {
+        let written = File::create(path).and_then(|file| {
+            let writer = BufWriter::new(file);
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("json") => trace::write_json(traces, writer),
+                _ => trace::write_csv(traces, writer),
             }
-            total_counter += 1_f64;
-            
-            // Update texture
-            texture.update(&mut texture_context, &canvas).unwrap();
-            image(&texture, c.transform, g);
-            texture_context.encoder.flush(device);
-
-            // Draw text for pi approximation
-            let transform = c.transform.trans(10.0, 535.0);
-
-            text::Text::new_color([0.0, 0.0, 1.0, 1.0], 28).draw(
-                &format!("{}", (4_f64 * inside_counter) / (total_counter as f64)).to_string(),
-                &mut glyphs,
-                &c.draw_state,
-                transform, g
-            ).unwrap();
-
-            // Update glyphs before rendering.
-            glyphs.factory.encoder.flush(device);
         });
+        if let Err(error) = written {
+            eprintln!(
+                "error: could not write trace to {}: {}",
+                path.display(),
+                error
+            );
+            std::process::exit(1);
+        }
     }
 }
 
-fn map(val: f64, min: f64, max: f64, new_min: f64, new_max: f64) -> f64 {
-    // Map val to new val based on new range
-    let range = max - min;
-    let new_range = new_max - new_min;
-    (val / range) * new_range + new_min
+// Trace the circle and the needles with every kind of points and report how fast their errors shrink
+fn compare_points(options: &Options, seed: u64, sampler: &ParallelSampler) -> Vec<Trace> {
+    let samples = options.samples.unwrap_or(1_000_000);
+    let mut traces = Vec::new();
+    if options.method.includes(Method::Buffon) {
+        traces.extend(trace_each_points(options, sampler, samples, |points| {
+            buffon_estimator(options, points)
+        }));
+    }
+    if options.runs_laplace() {
+        traces.extend(trace_each_points(options, sampler, samples, |points| {
+            laplace_estimator(options, points)
+        }));
+    }
+    if options.method.includes(Method::Noodle) {
+        traces.extend(trace_each_points(options, sampler, samples, |points| {
+            noodle_estimator(options, seed, points)
+        }));
+    }
+    if options.method.includes(Method::Circle) {
+        traces.extend(trace_each_points(options, sampler, samples, |points| {
+            circle_estimator(options, points)
+        }));
+    }
+    print_error_decay(options.format, seed, &traces);
+    note_skipped(options);
+    traces
 }
 
-fn buffons_needle(iterations: u64) -> f64 {
-    // If a needle of length l is dropped n times on a surface on which parallel lines...
-    // ...are drawn t units appart, and if x of those comes to rest crossing a line...
-    // ...then pi ~ 2nl/xt
-    
-    let needle_length = 1_f64;
-    let parallel_width = 1_f64;
-    let two_pi = 6.28318530718_f64;
-
-    let mut cross_counter = 0_f64;
+// Trace `samples` samples of `estimator(points)` for every kind of points
+fn trace_each_points<E, F>(
+    options: &Options,
+    sampler: &ParallelSampler,
+    samples: u64,
+    estimator: F,
+) -> Vec<Trace>
+where
+    E: PiEstimator + Clone + Send + Sync,
+    F: Fn(PointSource) -> E,
+{
+    let mut traces = Vec::new();
+    for &points in &[PointSource::Random, PointSource::Halton, PointSource::Sobol] {
+        let mut estimator = estimator(points);
+        let mut sampler = sampler.clone();
+        traces.push(Trace::record(
+            &mut estimator,
+            samples,
+            options.trace_points,
+            |estimator, n| sampler.sample(estimator, n),
+        ));
+    }
 
-    let mut rng = rand::thread_rng();
-    for _ in 0..iterations {
-        // Only care about the x position since the y position doesn't affect the outcome
-        let needle_start_x = rng.gen_range(0_f64, parallel_width);
+    // A Hammersley set is only spread evenly once all of it is drawn, so every checkpoint gets its own
+    let mut hammersley = Trace {
+        method: String::new(),
+        points: Vec::new(),
+    };
+    for checkpoint in trace::checkpoints(samples, options.trace_points) {
+        let mut estimator = estimator(PointSource::Hammersley { points: checkpoint });
+        sampler.clone().sample(&mut estimator, checkpoint);
+        let estimate = estimator.estimate();
+        hammersley.method = estimator.name();
+        hammersley.points.push(TracePoint {
+            samples: checkpoint,
+            estimate: estimate.value,
+            std_error: estimate.std_error,
+        });
+    }
+    traces.push(hammersley);
+    traces
+}
 
-        let angle = rng.gen_range(0_f64, two_pi);
-        let needle_end_x = needle_start_x + needle_length * f64::cos(angle);
+// Run the circle and Buffon's needle with every variance reduction and report
+// how much each one reduces the variance of a sample
+fn compare_variance(options: &Options, seed: u64, sampler: &ParallelSampler) -> Vec<Trace> {
+    let samples = options.samples.unwrap_or(1_000_000);
+    let points = point_source(options, samples);
+    let mut traces = Vec::new();
+    // Each method is compared with its first, plain, estimator
+    let mut methods: Vec<Vec<Box<dyn PiEstimator>>> = Vec::new();
+    if options.method.includes(Method::Buffon) {
+        let reductions = [
+            buffon::VarianceReduction::Crossings,
+            buffon::VarianceReduction::RaoBlackwell,
+            buffon::VarianceReduction::ImportanceSampling,
+        ];
+        methods.push(
+            reductions
+                .iter()
+                // Importance sampled angles need pi, so pi-free needles go without them
+                .filter_map(|&reduction| {
+                    buffon_estimator(options, points)
+                        .with_variance_reduction(reduction)
+                        .ok()
+                })
+                .map(|estimator| {
+                    let estimator =
+                        run_estimator(sampler.clone(), options, &mut traces, estimator, samples);
+                    Box::new(estimator) as Box<dyn PiEstimator>
+                })
+                .collect(),
+        );
+    }
+    if options.method.includes(Method::Circle) {
+        let reductions = [
+            VarianceReduction::HitOrMiss,
+            VarianceReduction::Antithetic,
+            VarianceReduction::Stratified { strata: STRATA },
+            VarianceReduction::LatinHypercube {
+                points: LATIN_HYPERCUBE_POINTS,
+            },
+            VarianceReduction::MeanValue,
+            VarianceReduction::ControlVariate,
+        ];
+        methods.push(
+            reductions
+                .iter()
+                .map(|&reduction| {
+                    let estimator = circle_estimator(options, points)
+                        .with_variance_reduction(reduction)
+                        .expect("every group is a power of two");
+                    let estimator =
+                        run_estimator(sampler.clone(), options, &mut traces, estimator, samples);
+                    Box::new(estimator) as Box<dyn PiEstimator>
+                })
+                .collect(),
+        );
+    }
+    print_variance_reduction(options.format, seed, &methods);
+    traces
+}
 
-        // If end of needle is outside of width then it has crossed a line
-        if needle_end_x < 0_f64 || needle_end_x > parallel_width {
-            cross_counter += 1_f64;
+fn render_visuals(options: &Options, seed: u64, path: &Path) {
+    let mut rng = approximating_pi::seeded_rng(seed);
+    let scenes = scenes(options, seed);
+    let several = scenes.len() > 1;
+    for (key, mut scene) in scenes {
+        let rate = options.rate.unwrap_or_else(|| scene.default_rate());
+        let gif = path.extension().and_then(|extension| extension.to_str()) == Some("gif");
+        // Keep the output of each method apart when there are several
+        let path = match (several, gif) {
+            (false, _) => path.to_path_buf(),
+            (true, true) => path.with_file_name(format!(
+                "{}-{}.gif",
+                path.file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or("pi"),
+                key
+            )),
+            (true, false) => path.join(key),
+        };
+        let written = if gif {
+            render::write_gif(
+                scene.as_mut(),
+                &mut rng,
+                options.frames,
+                options.frame_step,
+                rate,
+                GIF_FRAME_DELAY_MS,
+                &path,
+            )
+        } else {
+            render::write_png_frames(
+                scene.as_mut(),
+                &mut rng,
+                options.frames,
+                options.frame_step,
+                rate,
+                &path,
+            )
+        };
+        if let Err(error) = written {
+            eprintln!("error: could not render to {}: {}", path.display(), error);
+            std::process::exit(1);
         }
+        println!(
+            "{} (rendered to {}): {}",
+            scene.title(),
+            path.display(),
+            scene.caption()
+        );
+    }
+}
+
+#[cfg(feature = "gui")]
+fn show_visuals(options: &Options, seed: u64) {
+    let mut rng = approximating_pi::seeded_rng(seed);
+    for (_, mut scene) in scenes(options, seed) {
+        let rate = options.rate.unwrap_or_else(|| scene.default_rate());
+        gui::show(scene.as_mut(), &mut rng, rate);
+        // pi approximation is printed on the console once the window is closed
+        println!("{} (visuals): {}", scene.title(), scene.caption());
     }
+}
 
-    (2_f64 * (iterations as f64) * needle_length) / (cross_counter * parallel_width) 
+// The visuals of every selected method, each with a fresh estimator
+fn scenes(options: &Options, seed: u64) -> Vec<(&'static str, Box<dyn Scene>)> {
+    let mut scenes: Vec<(&'static str, Box<dyn Scene>)> = Vec::new();
+    if options.method.includes(Method::Walk) {
+        let estimator = RandomWalk::with_formula(options.steps, walk_formula(options));
+        scenes.push(("walk", Box::new(WalkScene::new(estimator, options.size))));
+    }
+    if options.method.includes(Method::Buffon) {
+        let points = point_source(options, options.samples.unwrap_or(1_000_000));
+        let estimator = buffon_estimator(options, points);
+        scenes.push((
+            "buffon",
+            Box::new(NeedleScene::new(estimator, options.size).with_seed(seed)),
+        ));
+    }
+    if options.runs_laplace() {
+        let points = point_source(options, options.samples.unwrap_or(1_000_000));
+        let estimator = laplace_estimator(options, points);
+        let scene = GridScene::new(estimator, options.size).with_seed(seed);
+        scenes.push(("laplace", Box::new(scene)));
+    }
+    if options.method.includes(Method::Noodle) {
+        let points = point_source(options, options.samples.unwrap_or(1_000_000));
+        let estimator = noodle_estimator(options, seed, points);
+        scenes.push((
+            "noodle",
+            Box::new(NoodleScene::new(estimator, options.size).with_seed(seed)),
+        ));
+    }
+    if options.method.includes(Method::Circle) {
+        let points = point_source(options, options.samples.unwrap_or(1_000_000));
+        let estimator = circle_estimator(options, points);
+        let scene = CircleScene::new(estimator, options.size)
+            .with_colors(options.hit_color, options.miss_color);
+        scenes.push(("circle", Box::new(scene)));
+    }
+    scenes
 }
 
-fn random_walk(steps: u64, walks: u64) -> f64 {
-    // 1. Start a walk at position 0
-    // 2. Generate a number between 0 and 1
-    // 3. If number is less than 0.5, move position of x in the positive direction
-    // 4. Else move it in the negative direction
-    // 5. Do this step number of times
-    // 6. Calculate absolute distance from origin and sum it cumulatively
-    // 7. Do this walk number of times
-    // 8. Average the number of absolute distances
-    // 9. pi ~ 2 * steps / average_distance^2 
+fn walk_formula(options: &Options) -> Formula {
+    if options.asymptotic {
+        Formula::Asymptotic
+    } else {
+        Formula::Exact
+    }
+}
 
-    let mut sum_of_abs_distances = 0_f64;
+fn buffon_estimator(options: &Options, points: PointSource) -> BuffonsNeedle {
+    BuffonsNeedle::with_lengths(options.needle_length, options.line_spacing)
+        .and_then(|estimator| {
+            estimator
+                .with_points(points)
+                .with_variance_reduction(options.buffon_reduction)
+        })
+        .and_then(|estimator| estimator.with_direction(buffon_direction(options)))
+        .expect("needle length, line spacing and pi-free angles are checked when parsing")
+}
 
-    let mut rng = rand::thread_rng();
-    for _ in 0..walks {
-        let mut position = 0_f64;
-        for _ in 0..steps {
-            let flip = rng.gen_range(0_f64, 1_f64);
-    
-            if flip < 0.5f64 {
-                position += 1_f64;
-            } else {
-                position -= 1_f64;
+fn buffon_direction(options: &Options) -> Direction {
+    if options.pi_free {
+        Direction::Disk
+    } else {
+        Direction::Angle
+    }
+}
+
+fn laplace_estimator(options: &Options, points: PointSource) -> BuffonLaplace {
+    BuffonLaplace::with_lengths(
+        options.needle_length,
+        options.line_spacing,
+        options.cell_height(),
+    )
+    .expect("needle length and grid cells are checked when parsing")
+    .with_points(points)
+    .with_direction(buffon_direction(options))
+}
+
+fn noodle_estimator(options: &Options, seed: u64, points: PointSource) -> BuffonsNoodle {
+    let noodle = match &options.noodle {
+        NoodleShape::Circle => Noodle::circle(0.5, NOODLE_SEGMENTS),
+        NoodleShape::Arc => Noodle::arc(1.0, std::f64::consts::PI, NOODLE_SEGMENTS),
+        NoodleShape::Polygon => {
+            let mut rng = rng::seeded_stream(seed, rng::SHAPE_STREAM);
+            Noodle::random_polygon(&mut rng, POLYGON_SIDES, 1.0)
+        }
+        NoodleShape::Vertices(noodle) => Ok(noodle.clone()),
+    };
+    noodle
+        .and_then(|noodle| BuffonsNoodle::new(noodle, options.line_spacing))
+        .expect("noodle shapes and line spacing are checked when parsing")
+        .with_points(points)
+        .with_direction(buffon_direction(options))
+}
+
+fn circle_estimator(options: &Options, points: PointSource) -> CircleInSquare {
+    let estimator = match options.pixel_grid {
+        Some(size) => CircleInSquare::pixels(size, size),
+        None => CircleInSquare::new(),
+    };
+    estimator
+        .with_points(points)
+        .with_variance_reduction(options.variance_reduction)
+        .expect("groups of darts are checked when parsing")
+}
+
+// The points chosen with --points, a Hammersley set being sized for `samples` samples
+fn point_source(options: &Options, samples: u64) -> PointSource {
+    match options.points {
+        PointSource::Hammersley { .. } => PointSource::Hammersley { points: samples },
+        points => points,
+    }
+}
+
+fn run_estimator<E>(
+    mut sampler: ParallelSampler,
+    options: &Options,
+    traces: &mut Vec<Trace>,
+    mut estimator: E,
+    samples: u64,
+) -> E
+where
+    E: PiEstimator + Clone + Send + Sync,
+{
+    if options.trace.is_some() {
+        let trace = Trace::record(
+            &mut estimator,
+            samples,
+            options.trace_points,
+            |estimator, n| sampler.sample(estimator, n),
+        );
+        traces.push(trace);
+    } else {
+        sampler.sample(&mut estimator, samples);
+    }
+    estimator
+}
+
+fn print_results(format: Format, seed: u64, estimators: &[Box<dyn PiEstimator>]) {
+    match format {
+        Format::Text => {
+            println!("seed = {}", seed);
+            for estimator in estimators {
+                let estimate = estimator.estimate();
+                println!(
+                    "{}: pi = {} (95% CI [{:.6}, {:.6}], n = {})",
+                    estimator.name(),
+                    estimate,
+                    estimate.confidence_interval.0,
+                    estimate.confidence_interval.1,
+                    estimate.samples
+                );
+            }
+        }
+        Format::Csv => {
+            println!("method,seed,samples,estimate,std_error,ci_low,ci_high");
+            for estimator in estimators {
+                let estimate = estimator.estimate();
+                println!(
+                    "{},{},{},{},{},{},{}",
+                    csv_field(&estimator.name()),
+                    seed,
+                    estimate.samples,
+                    estimate.value,
+                    estimate.std_error,
+                    estimate.confidence_interval.0,
+                    estimate.confidence_interval.1
+                );
             }
         }
-        // Distance from origin
-        let abs_distance = position.abs();
-        sum_of_abs_distances += abs_distance;
+        Format::Json => {
+            let rows: Vec<String> = estimators
+                .iter()
+                .map(|estimator| {
+                    let estimate = estimator.estimate();
+                    format!(
+                        "  {{\"method\": \"{}\", \"seed\": {}, \"samples\": {}, \"estimate\": {}, \"std_error\": {}, \"ci95\": [{}, {}]}}",
+                        estimator.name(),
+                        seed,
+                        estimate.samples,
+                        json_number(estimate.value),
+                        json_number(estimate.std_error),
+                        json_number(estimate.confidence_interval.0),
+                        json_number(estimate.confidence_interval.1)
+                    )
+                })
+                .collect();
+            println!("[\n{}\n]", rows.join(",\n"));
+        }
     }
+}
 
-    let average_sum_of_abs_distances = sum_of_abs_distances / (walks as f64);
+fn print_error_decay(format: Format, seed: u64, traces: &[Trace]) {
+    let rows = traces.iter().filter_map(|trace| {
+        let last = trace.points.last()?;
+        let error = last.estimate - std::f64::consts::PI;
+        Some((trace, last, error, trace.error_decay_rate()))
+    });
+    match format {
+        Format::Text => {
+            println!("seed = {}", seed);
+            for (trace, last, error, rate) in rows {
+                println!(
+                    "{}: pi = {:.6} (error {:+.2e}, n = {}), error ~ n^{:.2}",
+                    trace.method, last.estimate, error, last.samples, rate
+                );
+            }
+        }
+        Format::Csv => {
+            println!("method,seed,samples,estimate,error,error_decay_rate");
+            for (trace, last, error, rate) in rows {
+                println!(
+                    "{},{},{},{},{},{}",
+                    csv_field(&trace.method),
+                    seed,
+                    last.samples,
+                    last.estimate,
+                    error,
+                    rate
+                );
+            }
+        }
+        Format::Json => {
+            let rows: Vec<String> = rows
+                .map(|(trace, last, error, rate)| {
+                    format!(
+                        "  {{\"method\": \"{}\", \"seed\": {}, \"samples\": {}, \"estimate\": {}, \"error\": {}, \"error_decay_rate\": {}}}",
+                        trace.method,
+                        seed,
+                        last.samples,
+                        json_number(last.estimate),
+                        json_number(error),
+                        json_number(rate)
+                    )
+                })
+                .collect();
+            println!("[\n{}\n]", rows.join(",\n"));
+        }
+    }
+}
 
-    // pi = 2 * n / (d_avg^2)
-    (2_f64 * (steps as f64)) / (average_sum_of_abs_distances.powf(2_f64))
-}
\ No newline at end of file
+// Variance of a single sample of each estimator, and how many times smaller it
+// is than that of the first estimator of its method
+fn print_variance_reduction(format: Format, seed: u64, methods: &[Vec<Box<dyn PiEstimator>>]) {
+    let variance = |estimator: &dyn PiEstimator| {
+        let estimate = estimator.estimate();
+        estimate.std_error.powi(2) * estimate.samples as f64
+    };
+    let rows = methods.iter().flat_map(|estimators| {
+        let baseline = estimators
+            .first()
+            .map(|estimator| variance(estimator.as_ref()))
+            .unwrap_or(f64::NAN);
+        estimators.iter().map(move |estimator| {
+            let variance = variance(estimator.as_ref());
+            (
+                estimator,
+                estimator.estimate(),
+                variance,
+                baseline / variance,
+            )
+        })
+    });
+    match format {
+        Format::Text => {
+            println!("seed = {}", seed);
+            for (estimator, estimate, variance, reduction) in rows {
+                println!(
+                    "{}: pi = {} (n = {}), variance per sample {:.4}, {:.2}x less",
+                    estimator.name(),
+                    estimate,
+                    estimate.samples,
+                    variance,
+                    reduction
+                );
+            }
+        }
+        Format::Csv => {
+            println!(
+                "method,seed,samples,estimate,std_error,variance_per_sample,variance_reduction"
+            );
+            for (estimator, estimate, variance, reduction) in rows {
+                println!(
+                    "{},{},{},{},{},{},{}",
+                    csv_field(&estimator.name()),
+                    seed,
+                    estimate.samples,
+                    estimate.value,
+                    estimate.std_error,
+                    variance,
+                    reduction
+                );
+            }
+        }
+        Format::Json => {
+            let rows: Vec<String> = rows
+                .map(|(estimator, estimate, variance, reduction)| {
+                    format!(
+                        "  {{\"method\": \"{}\", \"seed\": {}, \"samples\": {}, \"estimate\": {}, \"std_error\": {}, \"variance_per_sample\": {}, \"variance_reduction\": {}}}",
+                        estimator.name(),
+                        seed,
+                        estimate.samples,
+                        json_number(estimate.value),
+                        json_number(estimate.std_error),
+                        json_number(variance),
+                        json_number(reduction)
+                    )
+                })
+                .collect();
+            println!("[\n{}\n]", rows.join(",\n"));
+        }
+    }
+}
@@ -0,0 +1,407 @@
+use std::f64::consts::TAU;
+use std::fs;
+use std::path::Path;
+
+use rand::{Rng, RngCore};
+
+use crate::buffon::{disk_direction, Direction};
+use crate::estimator::{qualified_name, Estimate, PiEstimator};
+use crate::map;
+use crate::points::{PointCursor, PointSource};
+
+// Buffon's noodle: however a curve of length L is bent, it crosses lines drawn
+// t units apart 2L / (pi t) times on average. Every small piece of it is a short
+// needle and the expected crossings of the pieces add up. So pi ~ 2nL/xt after
+// x crossings in n drops, the shape only changing how much x varies.
+
+/// The shape of a noodle: a polyline, made of straight pieces between its vertices.
+///
+/// Closed shapes end with their first vertex.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Noodle {
+    vertices: Vec<[f64; 2]>,
+    length: f64,
+    // Middle of the bounding box, which the noodle is turned about
+    centre: [f64; 2],
+    shape: &'static str,
+}
+
+impl Noodle {
+    /// The polyline through `vertices`, in order.
+    pub fn new(vertices: Vec<[f64; 2]>) -> Result<Self, String> {
+        if vertices.len() < 2 {
+            return Err(format!(
+                "a noodle needs at least 2 vertices, not {}",
+                vertices.len()
+            ));
+        }
+        if let Some(vertex) = vertices
+            .iter()
+            .find(|vertex| !(vertex[0].is_finite() && vertex[1].is_finite()))
+        {
+            return Err(format!(
+                "the vertices of a noodle must be numbers, not {:?}",
+                vertex
+            ));
+        }
+        let length: f64 = vertices
+            .windows(2)
+            .map(|piece| (piece[1][0] - piece[0][0]).hypot(piece[1][1] - piece[0][1]))
+            .sum();
+        if length <= 0_f64 {
+            return Err("a noodle must have some length".to_string());
+        }
+
+        let mut low = vertices[0];
+        let mut high = vertices[0];
+        for vertex in &vertices {
+            for axis in 0..2 {
+                low[axis] = low[axis].min(vertex[axis]);
+                high[axis] = high[axis].max(vertex[axis]);
+            }
+        }
+        Ok(Noodle {
+            vertices,
+            length,
+            centre: [(low[0] + high[0]) / 2_f64, (low[1] + high[1]) / 2_f64],
+            shape: "polyline",
+        })
+    }
+
+    // The same noodle, named `shape` in the output
+    fn with_shape(mut self, shape: &'static str) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// A closed regular polygon of `segments` sides in a circle of radius `radius`.
+    ///
+    /// Its vertices are placed with pi, and its length is close to pi times its
+    /// diameter: a circle as wide as the lines are apart crosses them twice on
+    /// almost every drop, so the estimate mostly gives back the pi it was built with.
+    pub fn circle(radius: f64, segments: u32) -> Result<Self, String> {
+        Self::arc(radius, TAU, segments).map(|noodle| noodle.with_shape("circle"))
+    }
+
+    /// `segments` equal pieces along an arc of radius `radius` turning through `angle` radians.
+    ///
+    /// An angle in radians usually comes from pi, and so does the length of the arc.
+    pub fn arc(radius: f64, angle: f64, segments: u32) -> Result<Self, String> {
+        let segments = segments.max(1);
+        let vertices = (0..=segments)
+            .map(|i| {
+                let turned = angle * i as f64 / segments as f64;
+                [radius * turned.cos(), radius * turned.sin()]
+            })
+            .collect();
+        Self::new(vertices).map(|noodle| noodle.with_shape("arc"))
+    }
+
+    /// A closed polygon with `sides` vertices in random directions around the
+    /// origin, between `radius / 2` and `radius` away from it, drawing from `rng`.
+    ///
+    /// The directions are drawn like [`Direction::Disk`], so nothing in the shape depends on pi.
+    pub fn random_polygon(rng: &mut dyn RngCore, sides: u32, radius: f64) -> Result<Self, String> {
+        if !(radius > 0_f64 && radius.is_finite()) {
+            return Err(format!(
+                "the radius of a polygon must be a positive number, not {}",
+                radius
+            ));
+        }
+        let mut directions: Vec<(f64, f64)> =
+            (0..sides.max(3)).map(|_| disk_direction(rng)).collect();
+        // Going round the origin in order keeps the sides from crossing each other,
+        // atan2 only orders the directions
+        directions.sort_by(|a, b| a.1.atan2(a.0).total_cmp(&b.1.atan2(b.0)));
+        let mut vertices: Vec<[f64; 2]> = directions
+            .iter()
+            .map(|&(cos, sin)| {
+                let distance = rng.gen_range(radius / 2_f64, radius);
+                [distance * cos, distance * sin]
+            })
+            .collect();
+        vertices.push(vertices[0]);
+        Self::new(vertices).map(|noodle| noodle.with_shape("polygon"))
+    }
+
+    /// Read a noodle from a file of vertices, see [`Noodle::parse`].
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("could not read {}: {}", path.display(), error))?;
+        Self::parse(&text)
+            .map(|noodle| noodle.with_shape("file"))
+            .map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    /// A noodle from one vertex per line, written `x y` or `x, y`.
+    ///
+    /// Blank lines and anything after a `#` are ignored. A closed shape repeats
+    /// its first vertex at the end.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut vertices = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let coordinates: Vec<&str> = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|coordinate| !coordinate.is_empty())
+                .collect();
+            let vertex = match coordinates.as_slice() {
+                [x, y] => x.parse().ok().zip(y.parse().ok()),
+                _ => None,
+            };
+            match vertex {
+                Some((x, y)) => vertices.push([x, y]),
+                None => {
+                    return Err(format!(
+                        "line {}: expected a vertex 'x y', not '{}'",
+                        number + 1,
+                        line
+                    ))
+                }
+            }
+        }
+        Self::new(vertices)
+    }
+
+    pub fn vertices(&self) -> &[[f64; 2]] {
+        &self.vertices
+    }
+
+    /// Total length of the pieces.
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    /// Name of the shape used in the output: `circle`, `arc`, `polygon` for a
+    /// random one, `file` or `polyline`.
+    pub fn shape(&self) -> &'static str {
+        self.shape
+    }
+}
+
+/// Buffon's noodle: drop a bent needle on parallel lines and count every line it crosses.
+///
+/// One sample is one drop. The position and angle of each drop are
+/// pseudo-random unless another [`PointSource`] is chosen with
+/// [`BuffonsNoodle::with_points`].
+#[derive(Debug, Clone)]
+pub struct BuffonsNoodle {
+    noodle: Noodle,
+    parallel_width: f64,
+    points: PointCursor,
+    direction: Direction,
+    drops: u64,
+    crossings: u64,
+    // Sum of the squared crossings of each drop
+    crossing_squares: u64,
+}
+
+impl BuffonsNoodle {
+    /// Drop `noodle` on lines `parallel_width` apart.
+    pub fn new(noodle: Noodle, parallel_width: f64) -> Result<Self, String> {
+        if !(parallel_width > 0_f64 && parallel_width.is_finite()) {
+            return Err(format!(
+                "the line spacing must be a positive number, not {}",
+                parallel_width
+            ));
+        }
+        Ok(BuffonsNoodle {
+            noodle,
+            parallel_width,
+            points: PointCursor::default(),
+            direction: Direction::Angle,
+            drops: 0,
+            crossings: 0,
+            crossing_squares: 0,
+        })
+    }
+
+    /// Drop the noodles at the points of `points` instead of pseudo-random ones.
+    pub fn with_points(mut self, points: PointSource) -> Self {
+        self.points = PointCursor::new(points);
+        self
+    }
+
+    /// Turn the noodles with `direction`, e.g. without using pi.
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn points(&self) -> PointSource {
+        self.points.source()
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn noodle(&self) -> &Noodle {
+        &self.noodle
+    }
+
+    pub fn parallel_width(&self) -> f64 {
+        self.parallel_width
+    }
+
+    /// Number of times a noodle came to rest crossing a line, counting every line it crosses.
+    pub fn crossings(&self) -> u64 {
+        self.crossings
+    }
+
+    /// Drop `n` more noodles like [`PiEstimator::sample`], calling `observe` with
+    /// the vertices of each one and the number of lines it crosses.
+    ///
+    /// The middle of each noodle lies between the line at `x = 0` and the next one.
+    pub fn sample_with<F>(&mut self, rng: &mut dyn RngCore, n: u64, mut observe: F)
+    where
+        F: FnMut(&[[f64; 2]], u64),
+    {
+        let mut placed = Vec::with_capacity(self.noodle.vertices.len());
+        for _ in 0..n {
+            let [u, v] = match self.direction {
+                Direction::Angle => self.points.point(rng),
+                // The direction doesn't come from the points
+                Direction::Disk => {
+                    let [u] = self.points.point(rng);
+                    [u, 0_f64]
+                }
+            };
+            self.points.advance();
+            let centre_x = map(u, 0_f64, 1_f64, 0_f64, self.parallel_width);
+            let (cos, sin) = match self.direction {
+                Direction::Angle => {
+                    let angle = map(v, 0_f64, 1_f64, 0_f64, TAU);
+                    (angle.cos(), angle.sin())
+                }
+                Direction::Disk => disk_direction(rng),
+            };
+
+            // Turn the noodle about its middle and move the middle to centre_x
+            let [middle_x, middle_y] = self.noodle.centre;
+            placed.clear();
+            placed.extend(self.noodle.vertices.iter().map(|&[x, y]| {
+                let (x, y) = (x - middle_x, y - middle_y);
+                [centre_x + x * cos - y * sin, x * sin + y * cos]
+            }));
+
+            // Each piece crosses the lines between the strips its ends lie in
+            let crossings: u64 = placed
+                .windows(2)
+                .map(|piece| {
+                    let strip = |x: f64| (x / self.parallel_width).floor();
+                    (strip(piece[1][0]) - strip(piece[0][0])).abs() as u64
+                })
+                .sum();
+            self.crossings += crossings;
+            self.crossing_squares += crossings * crossings;
+            observe(&placed, crossings);
+        }
+        self.drops += n;
+    }
+}
+
+impl PiEstimator for BuffonsNoodle {
+    fn name(&self) -> String {
+        // The shape is named first, then a pi-free direction, then the points
+        let shape = Some(self.noodle.shape());
+        let pi_free = (self.direction == Direction::Disk).then_some("pi-free");
+        let points = self.points.source().qualifier();
+        qualified_name("buffons noodle", &[shape, pi_free, points])
+    }
+
+    fn sample(&mut self, rng: &mut dyn RngCore, n: u64) {
+        self.sample_with(rng, n, |_, _| {});
+    }
+
+    fn estimate(&self) -> Estimate {
+        // pi = 2L / (t * m) with m the average number of crossings, and its delta method standard error
+        let n = self.drops as f64;
+        let mean = self.crossings as f64 / n;
+        let value = (2_f64 * self.noodle.length) / (mean * self.parallel_width);
+        let variance = (self.crossing_squares as f64 - n * mean * mean) / (n - 1_f64);
+        let std_error = value * (variance / n).sqrt() / mean;
+        Estimate::new(value, self.drops, std_error)
+    }
+
+    fn sample_count(&self) -> u64 {
+        self.drops
+    }
+
+    fn reset(&mut self) {
+        self.points.skip_to(0);
+        self.drops = 0;
+        self.crossings = 0;
+        self.crossing_squares = 0;
+    }
+
+    fn skip_to(&mut self, index: u64) {
+        self.points.skip_to(index);
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.points.merge(&other.points);
+        self.drops += other.drops;
+        self.crossings += other.crossings;
+        self.crossing_squares += other.crossing_squares;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::seeded_rng;
+
+    #[test]
+    fn parse_reads_vertices_and_skips_comments() {
+        let noodle = Noodle::parse("# a square\n0 0\n1, 0\n\n1 1  # corner\n0,1\n0 0\n").unwrap();
+        assert_eq!(
+            noodle.vertices(),
+            &[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0], [0.0, 0.0]]
+        );
+        assert_eq!(noodle.length(), 4.0);
+    }
+
+    #[test]
+    fn parse_reports_the_bad_line() {
+        assert_eq!(
+            Noodle::parse("0 0\n1 x\n"),
+            Err("line 2: expected a vertex 'x y', not '1 x'".to_string())
+        );
+        assert!(Noodle::parse("0 0 0\n1 1\n").is_err());
+        assert!(Noodle::parse("0 0\nnan 1\n").is_err());
+    }
+
+    #[test]
+    fn a_noodle_needs_two_vertices_and_some_length() {
+        assert!(Noodle::parse("").is_err());
+        assert!(Noodle::parse("# nothing\n1 1\n").is_err());
+        assert!(Noodle::parse("1 1\n1 1\n").is_err());
+    }
+
+    #[test]
+    fn random_polygons_are_closed() {
+        let polygon = Noodle::random_polygon(&mut seeded_rng(1), 8, 1.0).unwrap();
+        let vertices = polygon.vertices();
+        assert_eq!(vertices.len(), 9);
+        assert_eq!(vertices[0], vertices[8]);
+        for vertex in vertices {
+            let distance = vertex[0].hypot(vertex[1]);
+            assert!((0.5..=1.0).contains(&distance), "{:?}", vertex);
+        }
+    }
+
+    #[test]
+    fn random_polygons_estimate_pi() {
+        let polygon = Noodle::random_polygon(&mut seeded_rng(1), 12, 1.0).unwrap();
+        let mut estimator = BuffonsNoodle::new(polygon, 1.0).unwrap();
+        estimator.sample(&mut seeded_rng(2), 100_000);
+        let estimate = estimator.estimate();
+        let error = (estimate.value - std::f64::consts::PI).abs();
+        assert!(error < 4.0 * estimate.std_error, "{}", estimate);
+        assert_eq!(estimator.name(), "buffons noodle (polygon)");
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::estimator::PiEstimator;
+use crate::rng::{seeded_rng, SeededRng};
+
+/// Number of samples drawn from each random number stream.
+pub const BLOCK_SIZE: u64 = 1 << 16;
+
+/// Splits sampling across worker threads while keeping runs reproducible.
+///
+/// Samples are cut into blocks of [`BLOCK_SIZE`]. Block `i` always draws from
+/// stream `i` of the generator seeded with `seed`, whichever thread runs it,
+/// and the partial totals are merged in block order. The result for a given
+/// seed is therefore the same for any number of threads.
+#[derive(Debug, Clone)]
+pub struct ParallelSampler {
+    seed: u64,
+    threads: usize,
+    next_block: u64,
+    // A block started by a previous call: its stream and how many samples it has left
+    unfinished: Option<(SeededRng, u64)>,
+}
+
+impl ParallelSampler {
+    pub fn new(seed: u64, threads: usize) -> Self {
+        ParallelSampler {
+            seed,
+            threads: threads.max(1),
+            next_block: 0,
+            unfinished: None,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Draw `n` more samples into `estimator`.
+    ///
+    /// Successive calls carry on from where the previous call stopped, so
+    /// drawing the samples in several batches gives the same totals as
+    /// drawing them all at once.
+    pub fn sample<E>(&mut self, estimator: &mut E, mut n: u64)
+    where
+        E: PiEstimator + Clone + Send + Sync,
+    {
+        // 1. Finish the block left over by the previous call
+        if let Some((mut rng, left)) = self.unfinished.take() {
+            let samples = left.min(n);
+            estimator.sample(&mut rng, samples);
+            n -= samples;
+            if samples < left {
+                self.unfinished = Some((rng, left - samples));
+                return;
+            }
+        }
+
+        // 2. Whole blocks are shared between the worker threads
+        let blocks = n / BLOCK_SIZE;
+        self.sample_blocks(estimator, blocks);
+
+        // 3. Start a new block with whatever is left and keep it for the next call
+        let remainder = n % BLOCK_SIZE;
+        if remainder > 0 {
+            let mut rng = self.block_rng(self.next_block);
+            self.next_block += 1;
+            estimator.sample(&mut rng, remainder);
+            self.unfinished = Some((rng, BLOCK_SIZE - remainder));
+        }
+    }
+
+    fn sample_blocks<E>(&mut self, estimator: &mut E, blocks: u64)
+    where
+        E: PiEstimator + Clone + Send + Sync,
+    {
+        let first_block = self.next_block;
+        self.next_block += blocks;
+
+        let mut empty = estimator.clone();
+        empty.reset();
+
+        let sample_block = |block: u64| {
+            let mut partial = empty.clone();
+            // Quasi-random points carry on from where the previous block stopped
+            partial.skip_to((first_block + block) * BLOCK_SIZE);
+            partial.sample(&mut self.block_rng(first_block + block), BLOCK_SIZE);
+            partial
+        };
+
+        if self.threads == 1 || blocks <= 1 {
+            for block in 0..blocks {
+                estimator.merge(&sample_block(block));
+            }
+            return;
+        }
+
+        // Workers take the next unclaimed block until none are left
+        let next = AtomicU64::new(0);
+        let merged = Mutex::new(InOrder {
+            estimator,
+            next_block: 0,
+            waiting: BTreeMap::new(),
+        });
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(blocks as usize) {
+                scope.spawn(|| loop {
+                    let block = next.fetch_add(1, Ordering::Relaxed);
+                    if block >= blocks {
+                        break;
+                    }
+                    let partial = sample_block(block);
+                    merged.lock().unwrap().merge(block, partial);
+                });
+            }
+        });
+    }
+
+    fn block_rng(&self, block: u64) -> SeededRng {
+        let mut rng = seeded_rng(self.seed);
+        rng.set_stream(block);
+        rng
+    }
+}
+
+// Merges the partial totals of the blocks in block order as they finish, only
+// keeping those that finished before an earlier block
+struct InOrder<'a, E> {
+    estimator: &'a mut E,
+    next_block: u64,
+    waiting: BTreeMap<u64, E>,
+}
+
+impl<E: PiEstimator> InOrder<'_, E> {
+    fn merge(&mut self, block: u64, partial: E) {
+        self.waiting.insert(block, partial);
+        while let Some(partial) = self.waiting.remove(&self.next_block) {
+            self.estimator.merge(&partial);
+            self.next_block += 1;
+        }
+    }
+}
+
+/// Number of threads to use when none is given: one per available CPU.
+pub fn default_threads() -> usize {
+    thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circle::{CircleInSquare, VarianceReduction};
+    use crate::points::PointSource;
+
+    // Not a whole number of blocks, so the last block is left unfinished
+    const SAMPLES: u64 = 5 * BLOCK_SIZE + 1234;
+
+    fn estimators() -> Vec<CircleInSquare> {
+        vec![
+            CircleInSquare::new(),
+            CircleInSquare::new().with_points(PointSource::Sobol),
+            CircleInSquare::new()
+                .with_variance_reduction(VarianceReduction::LatinHypercube { points: 256 })
+                .unwrap(),
+        ]
+    }
+
+    #[test]
+    fn same_seed_gives_same_totals_for_any_thread_count() {
+        for estimator in estimators() {
+            let estimates: Vec<_> = [1, 3, 8]
+                .iter()
+                .map(|&threads| {
+                    let mut estimator = estimator.clone();
+                    ParallelSampler::new(42, threads).sample(&mut estimator, SAMPLES);
+                    estimator.estimate()
+                })
+                .collect();
+            assert_eq!(estimates[0], estimates[1], "{}", estimator.name());
+            assert_eq!(estimates[0], estimates[2], "{}", estimator.name());
+        }
+    }
+
+    #[test]
+    fn batches_give_same_totals_as_one_call() {
+        for estimator in estimators() {
+            let mut at_once = estimator.clone();
+            ParallelSampler::new(7, 3).sample(&mut at_once, SAMPLES);
+
+            let mut batched = estimator.clone();
+            let mut sampler = ParallelSampler::new(7, 3);
+            for batch in [
+                1000,
+                2 * BLOCK_SIZE + 500,
+                17,
+                SAMPLES - 2 * BLOCK_SIZE - 1517,
+            ] {
+                sampler.sample(&mut batched, batch);
+            }
+            assert_eq!(batched.sample_count(), SAMPLES);
+            assert_eq!(
+                batched.estimate(),
+                at_once.estimate(),
+                "{}",
+                estimator.name()
+            );
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use std::sync::OnceLock;
+
+use rand::{Rng, RngCore};
+
+// Points of the unit cube [0, 1)^d that the estimators turn into samples.
+//
+// Pseudo-random points have errors shrinking like 1 / sqrt(n). Low discrepancy
+// sequences fill the cube more evenly, so for smooth enough problems the error
+// of quasi-Monte Carlo shrinks closer to 1 / n.
+
+/// Most dimensions a quasi-random point can have.
+pub const MAX_DIMENSIONS: usize = 10;
+
+// Bases of the Halton sequence, one prime per dimension
+const PRIMES: [u64; MAX_DIMENSIONS] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29];
+
+// Joe and Kuo's Sobol direction numbers (new-joe-kuo-6.21201) for dimensions 2 to 10:
+// degree s of the primitive polynomial, its coefficients a and the initial numbers m
+const SOBOL_PARAMETERS: [(usize, u32, &[u32]); MAX_DIMENSIONS - 1] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+];
+const SOBOL_BITS: usize = 32;
+
+/// Where the uniform numbers behind each sample come from.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PointSource {
+    /// Pseudo-random numbers from the `rng` passed to the estimator.
+    #[default]
+    Random,
+    /// The Halton sequence: radical inverses in the first prime bases.
+    Halton,
+    /// The Sobol sequence in base 2, built from Joe and Kuo's direction numbers.
+    ///
+    /// It repeats after 2^32 points.
+    Sobol,
+    /// The Hammersley set of `points` points: `i / points` followed by the
+    /// Halton sequence in one dimension fewer.
+    ///
+    /// It is only evenly spread once all `points` points are drawn, later points start it again.
+    Hammersley { points: u64 },
+}
+
+impl PointSource {
+    /// Name used in the output, e.g. `sobol`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PointSource::Random => "random",
+            PointSource::Halton => "halton",
+            PointSource::Sobol => "sobol",
+            PointSource::Hammersley { .. } => "hammersley",
+        }
+    }
+
+    /// Name to add to the name of an estimator, none for pseudo-random points.
+    pub fn qualifier(&self) -> Option<&'static str> {
+        self.is_quasi_random().then_some(self.name())
+    }
+
+    /// Whether the points are quasi-random, i.e. depend on their index rather than on `rng`.
+    pub fn is_quasi_random(&self) -> bool {
+        *self != PointSource::Random
+    }
+
+    /// Point number `index` of the sequence, a point in `[0, 1)^D`.
+    ///
+    /// [`PointSource::Random`] ignores `index` and draws `D` numbers from `rng` instead.
+    ///
+    /// # Panics
+    ///
+    /// If `D` is more than [`MAX_DIMENSIONS`] for a quasi-random source.
+    pub fn point<const D: usize>(&self, index: u64, rng: &mut dyn RngCore) -> [f64; D] {
+        let mut point = [0_f64; D];
+        for (dimension, coordinate) in point.iter_mut().enumerate() {
+            *coordinate = match *self {
+                PointSource::Random => rng.gen_range(0_f64, 1_f64),
+                PointSource::Halton => radical_inverse(index, PRIMES[dimension]),
+                PointSource::Sobol => sobol(index, dimension),
+                PointSource::Hammersley { points } => hammersley(index, dimension, points),
+            };
+        }
+        point
+    }
+}
+
+/// A [`PointSource`] and the position in the run of the next sample.
+///
+/// Quasi-random points depend on their position, which carries on from one
+/// block of a [`ParallelSampler`](crate::ParallelSampler) to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PointCursor {
+    source: PointSource,
+    next_index: u64,
+}
+
+impl PointCursor {
+    pub fn new(source: PointSource) -> Self {
+        PointCursor {
+            source,
+            next_index: 0,
+        }
+    }
+
+    pub fn source(&self) -> PointSource {
+        self.source
+    }
+
+    /// The point of the next sample, see [`PointSource::point`].
+    pub fn point<const D: usize>(&self, rng: &mut dyn RngCore) -> [f64; D] {
+        self.source.point(self.next_index, rng)
+    }
+
+    /// Move on to the next sample.
+    pub fn advance(&mut self) {
+        self.next_index += 1;
+    }
+
+    /// Make the next sample the one at position `index`, see [`PiEstimator::skip_to`](crate::PiEstimator::skip_to).
+    pub fn skip_to(&mut self, index: u64) {
+        self.next_index = index;
+    }
+
+    /// Carry on after the samples of `other`, see [`PiEstimator::merge`](crate::PiEstimator::merge).
+    pub fn merge(&mut self, other: &PointCursor) {
+        // Blocks are merged in order, so the last one says where the run has got to
+        self.next_index = self.next_index.max(other.next_index);
+    }
+}
+
+/// Reflect the digits of `index` in `base` about the decimal point, e.g. 6 = 110 in base 2 gives 0.011.
+pub fn radical_inverse(mut index: u64, base: u64) -> f64 {
+    let mut result = 0_f64;
+    let mut digit_value = 1_f64 / base as f64;
+    while index > 0 {
+        result += digit_value * (index % base) as f64;
+        index /= base;
+        digit_value /= base as f64;
+    }
+    result
+}
+
+/// Coordinate `dimension` of point number `index` of the Sobol sequence.
+pub fn sobol(index: u64, dimension: usize) -> f64 {
+    let directions = &sobol_directions()[dimension];
+    let mut bits = 0_u32;
+    let mut index = index % (1_u64 << SOBOL_BITS);
+    let mut k = 0;
+    while index > 0 {
+        if index & 1 == 1 {
+            bits ^= directions[k];
+        }
+        index >>= 1;
+        k += 1;
+    }
+    bits as f64 / (1_u64 << SOBOL_BITS) as f64
+}
+
+/// Coordinate `dimension` of point number `index` of the Hammersley set of `points` points.
+pub fn hammersley(index: u64, dimension: usize, points: u64) -> f64 {
+    let points = points.max(1);
+    let index = index % points;
+    if dimension == 0 {
+        index as f64 / points as f64
+    } else {
+        radical_inverse(index, PRIMES[dimension - 1])
+    }
+}
+
+// Direction numbers of every dimension, as 32 bit fractions, worked out once
+fn sobol_directions() -> &'static [[u32; SOBOL_BITS]; MAX_DIMENSIONS] {
+    static DIRECTIONS: OnceLock<[[u32; SOBOL_BITS]; MAX_DIMENSIONS]> = OnceLock::new();
+    DIRECTIONS.get_or_init(|| {
+        let mut directions = [[0_u32; SOBOL_BITS]; MAX_DIMENSIONS];
+        // The first dimension is the van der Corput sequence in base 2
+        for (k, direction) in directions[0].iter_mut().enumerate() {
+            *direction = 1 << (SOBOL_BITS - 1 - k);
+        }
+        for (dimension, &(s, a, m)) in SOBOL_PARAMETERS.iter().enumerate() {
+            let v = &mut directions[dimension + 1];
+            for k in 0..s {
+                v[k] = m[k] << (SOBOL_BITS - 1 - k);
+            }
+            // v_k = a_1 v_(k-1) ^ ... ^ a_(s-1) v_(k-s+1) ^ v_(k-s) ^ (v_(k-s) >> s)
+            for k in s..SOBOL_BITS {
+                let mut value = v[k - s] ^ (v[k - s] >> s);
+                for j in 1..s {
+                    if (a >> (s - 1 - j)) & 1 == 1 {
+                        value ^= v[k - j];
+                    }
+                }
+                v[k] = value;
+            }
+        }
+        directions
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::seeded_rng;
+
+    #[test]
+    fn radical_inverse_reflects_the_digits() {
+        assert_eq!(radical_inverse(6, 2), 0.375);
+        assert_eq!(radical_inverse(0, 3), 0.0);
+        assert_eq!(radical_inverse(1, 3), 1.0 / 3.0);
+        assert!((radical_inverse(5, 3) - 7.0 / 9.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn first_sobol_points_match_joe_kuo() {
+        // The first points printed by Joe and Kuo's generator, which visits them in Gray code order
+        let reference = [
+            [0.0, 0.0, 0.0],
+            [0.5, 0.5, 0.5],
+            [0.75, 0.25, 0.25],
+            [0.25, 0.75, 0.75],
+            [0.375, 0.375, 0.625],
+            [0.875, 0.875, 0.125],
+            [0.625, 0.125, 0.875],
+            [0.125, 0.625, 0.375],
+        ];
+        let mut rng = seeded_rng(1);
+        for (n, expected) in reference.iter().enumerate() {
+            let gray = (n ^ (n >> 1)) as u64;
+            assert_eq!(&PointSource::Sobol.point::<3>(gray, &mut rng), expected);
+        }
+    }
+
+    #[test]
+    fn each_sobol_dimension_fills_every_interval() {
+        // The first 2^k points put one coordinate in each interval [i / 2^k, (i + 1) / 2^k)
+        const POINTS: u64 = 1 << 10;
+        for dimension in 0..MAX_DIMENSIONS {
+            let mut intervals: Vec<u64> = (0..POINTS)
+                .map(|index| (sobol(index, dimension) * POINTS as f64) as u64)
+                .collect();
+            intervals.sort_unstable();
+            assert_eq!(intervals, (0..POINTS).collect::<Vec<_>>(), "{}", dimension);
+        }
+    }
+
+    #[test]
+    fn hammersley_starts_with_the_index_over_the_points() {
+        assert_eq!(hammersley(3, 0, 4), 0.75);
+        assert_eq!(hammersley(3, 1, 4), 0.75);
+        // Later points start the set again
+        assert_eq!(hammersley(7, 0, 4), 0.75);
+    }
+}
@@ -0,0 +1,263 @@
+use rand::{Rng, RngCore};
+
+use crate::estimator::{qualified_name, Estimate, PiEstimator};
+
+// 1. Start a walk at position 0
+// 2. Generate a number between 0 and 1
+// 3. If number is less than 0.5, move position of x in the positive direction
+// 4. Else move it in the negative direction
+// 5. Do this step number of times
+// 6. Calculate absolute distance from origin and sum it cumulatively
+// 7. Do this walk number of times
+// 8. Average the number of absolute distances
+// 9. pi ~ 2 * steps / average_distance^2
+//
+// Step 9 only holds as steps -> infinity. For a finite number of steps n = 2m or 2m - 1 the
+// expected distance is exactly E|S_n| = 2 * Gamma(m + 1/2) / (sqrt(pi) * Gamma(m)), so
+// 10. pi = (2 * Gamma(m + 1/2) / (Gamma(m) * average_distance))^2
+// removes the bias. This doesn't find pi from the walks alone: E|S_n| = 2m C(2m, m) / 4^m
+// is rational, so Gamma(m + 1/2) / Gamma(m) is sqrt(pi) times a rational number and step 10
+// is pi * (E|S_n| / average_distance)^2. Any exact inversion has pi inside it this way, the
+// walks only contribute the correction (E|S_n| / average_distance)^2, which tends to 1. With
+// a single step the distance is always 1, the correction is exactly 1 and nothing is left
+// but the constant, so the exact formula needs at least 2 steps.
+
+/// Which relation between the average distance and pi to invert.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Formula {
+    /// The exact finite-steps expectation of the distance, unbiased for any number of steps.
+    ///
+    /// Its Gamma ratio already holds sqrt(pi), so the walks only correct a
+    /// known value. It needs at least 2 steps, the estimate is NaN otherwise.
+    Exact,
+    /// The asymptotic E|S_n| ~ sqrt(2n / pi), biased by [`asymptotic_bias`] for finite steps.
+    Asymptotic,
+}
+
+/// 1-D random walks: pi from the average absolute distance travelled.
+///
+/// One sample is one whole walk of `steps` steps.
+#[derive(Debug, Clone)]
+pub struct RandomWalk {
+    steps: u64,
+    formula: Formula,
+    walks: u64,
+    sum_of_abs_distances: f64,
+    sum_of_squared_distances: f64,
+}
+
+impl RandomWalk {
+    /// Walks of `steps` steps, corrected with the exact expected distance.
+    pub fn new(steps: u64) -> Self {
+        Self::with_formula(steps, Formula::Exact)
+    }
+
+    /// Walks of `steps` steps, using the original asymptotic formula.
+    pub fn asymptotic(steps: u64) -> Self {
+        Self::with_formula(steps, Formula::Asymptotic)
+    }
+
+    pub fn with_formula(steps: u64, formula: Formula) -> Self {
+        RandomWalk {
+            steps,
+            formula,
+            walks: 0,
+            sum_of_abs_distances: 0_f64,
+            sum_of_squared_distances: 0_f64,
+        }
+    }
+
+    /// Number of steps taken in each walk.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn formula(&self) -> Formula {
+        self.formula
+    }
+
+    /// Take `n` more walks like [`PiEstimator::sample`], calling `observe(step, position)`
+    /// after every step. `step` counts from 1 to `steps` again for each walk.
+    pub fn sample_with<F>(&mut self, rng: &mut dyn RngCore, n: u64, mut observe: F)
+    where
+        F: FnMut(u64, i64),
+    {
+        for _ in 0..n {
+            let mut position = 0_f64;
+            for step in 1..=self.steps {
+                let flip = rng.gen_range(0_f64, 1_f64);
+
+                if flip < 0.5f64 {
+                    position += 1_f64;
+                } else {
+                    position -= 1_f64;
+                }
+                observe(step, position as i64);
+            }
+            // Distance from origin
+            let abs_distance = position.abs();
+            self.sum_of_abs_distances += abs_distance;
+            self.sum_of_squared_distances += abs_distance * abs_distance;
+        }
+        self.walks += n;
+    }
+}
+
+impl PiEstimator for RandomWalk {
+    fn name(&self) -> String {
+        let asymptotic = (self.formula == Formula::Asymptotic).then_some("asymptotic");
+        qualified_name("random walk", &[asymptotic])
+    }
+
+    fn sample(&mut self, rng: &mut dyn RngCore, n: u64) {
+        self.sample_with(rng, n, |_, _| {});
+    }
+
+    fn estimate(&self) -> Estimate {
+        let walks = self.walks as f64;
+        let average_sum_of_abs_distances = self.sum_of_abs_distances / walks;
+
+        if self.formula == Formula::Exact && self.steps < 2 {
+            // One step always ends 1 away, the estimate would be pi whatever the walks
+            return Estimate::new(f64::NAN, self.walks, f64::NAN);
+        }
+        let numerator = match self.formula {
+            // pi = (2 * Gamma(m + 1/2) / Gamma(m))^2 / (d_avg^2)
+            Formula::Exact => (2_f64 * gamma_half_ratio(half_steps(self.steps))).powi(2),
+            // pi = 2 * n / (d_avg^2)
+            Formula::Asymptotic => 2_f64 * (self.steps as f64),
+        };
+        let value = numerator / (average_sum_of_abs_distances.powf(2_f64));
+
+        // Delta method: se(pi) = |d pi / d d_avg| * se(d_avg) = 2 * pi * se(d_avg) / d_avg
+        let variance =
+            (self.sum_of_squared_distances / walks - average_sum_of_abs_distances.powi(2)) * walks
+                / (walks - 1_f64);
+        let std_error_of_average = (variance / walks).sqrt();
+        let std_error = 2_f64 * value * std_error_of_average / average_sum_of_abs_distances;
+
+        Estimate::new(value, self.walks, std_error)
+    }
+
+    fn sample_count(&self) -> u64 {
+        self.walks
+    }
+
+    fn reset(&mut self) {
+        self.walks = 0;
+        self.sum_of_abs_distances = 0_f64;
+        self.sum_of_squared_distances = 0_f64;
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.walks += other.walks;
+        self.sum_of_abs_distances += other.sum_of_abs_distances;
+        self.sum_of_squared_distances += other.sum_of_squared_distances;
+    }
+}
+
+/// Approximate pi from the average distance of `walks` 1-D random walks of `steps` steps each,
+/// drawing from `rng`.
+pub fn random_walk<R: Rng>(rng: &mut R, steps: u64, walks: u64) -> Estimate {
+    let mut estimator = RandomWalk::new(steps);
+    estimator.sample(rng, walks);
+    estimator.estimate()
+}
+
+/// Exact expected distance E|S_n| from the origin after `steps` steps.
+pub fn expected_distance(steps: u64) -> f64 {
+    2_f64 * gamma_half_ratio(half_steps(steps)) / std::f64::consts::PI.sqrt()
+}
+
+/// How far the [`Formula::Asymptotic`] estimate is from pi for `steps` steps, however many
+/// walks are averaged.
+pub fn asymptotic_bias(steps: u64) -> f64 {
+    2_f64 * (steps as f64) / expected_distance(steps).powi(2) - std::f64::consts::PI
+}
+
+// n = 2m and n = 2m - 1 steps have the same expected distance
+fn half_steps(steps: u64) -> u64 {
+    steps.div_ceil(2)
+}
+
+// Gamma(m + 1/2) / Gamma(m). The constant pi is not used, but the series carries
+// sqrt(pi) in its value and the recurrence only multiplies it by rational numbers.
+fn gamma_half_ratio(m: u64) -> f64 {
+    // Large m: asymptotic series, accurate to double precision from m = 64
+    fn series(x: f64) -> f64 {
+        x.sqrt()
+            * (1_f64 - 1_f64 / (8_f64 * x)
+                + 1_f64 / (128_f64 * x.powi(2))
+                + 5_f64 / (1024_f64 * x.powi(3))
+                - 21_f64 / (32768_f64 * x.powi(4))
+                - 399_f64 / (262_144_f64 * x.powi(5)))
+    }
+    const SERIES_FROM: u64 = 64;
+    if m >= SERIES_FROM {
+        return series(m as f64);
+    }
+
+    // Small m: recur down with Gamma(k + 1/2) / Gamma(k) = k / (k + 1/2) * Gamma(k + 3/2) / Gamma(k + 1)
+    let mut ratio = series(SERIES_FROM as f64);
+    for k in (m..SERIES_FROM).rev() {
+        ratio *= k as f64 / (k as f64 + 0.5_f64);
+    }
+    ratio
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::seeded_rng;
+
+    #[test]
+    fn seeded_estimate_is_exact() {
+        let estimate = random_walk(&mut seeded_rng(1), 100, 1000);
+        assert_eq!(estimate.value, 3.1805746445886616);
+        assert_eq!(estimate.samples, 1000);
+    }
+
+    #[test]
+    fn batches_give_same_estimate_as_one_call() {
+        let mut at_once = RandomWalk::new(100);
+        at_once.sample(&mut seeded_rng(2), 500);
+        let mut batched = RandomWalk::new(100);
+        let mut rng = seeded_rng(2);
+        batched.sample(&mut rng, 123);
+        batched.sample(&mut rng, 377);
+        assert_eq!(batched.estimate(), at_once.estimate());
+    }
+
+    #[test]
+    fn expected_distance_matches_binomial_sum() {
+        for steps in 1..=200_u64 {
+            // E|S_n| = sum over k heads of C(n, k) / 2^n * |2k - n|
+            let mut probability = 0.5_f64.powi(steps as i32);
+            let mut sum = 0_f64;
+            for heads in 0..=steps {
+                if heads > 0 {
+                    probability *= (steps - heads + 1) as f64 / heads as f64;
+                }
+                sum += probability * (2_f64 * heads as f64 - steps as f64).abs();
+            }
+            let error = (expected_distance(steps) - sum).abs() / sum;
+            assert!(
+                error < 1e-13,
+                "{} steps: {} != {}",
+                steps,
+                expected_distance(steps),
+                sum
+            );
+        }
+    }
+
+    #[test]
+    fn exact_formula_needs_two_steps() {
+        let mut estimator = RandomWalk::new(1);
+        estimator.sample(&mut seeded_rng(1), 100);
+        assert!(estimator.estimate().value.is_nan());
+        let mut estimator = RandomWalk::asymptotic(1);
+        estimator.sample(&mut seeded_rng(1), 100);
+        assert_eq!(estimator.estimate().value, 2.0);
+    }
+}
@@ -0,0 +1,163 @@
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+use rand::{Rng, RngCore};
+
+use super::draw::draw_line;
+use super::{placement_rng, Layout, Scene, BACKGROUND_COLOR};
+use crate::buffon::{BuffonsNeedle, Needle};
+use crate::estimator::{Estimate, PiEstimator};
+use crate::rng::SeededRng;
+
+// Number of gaps between the parallel lines across the canvas
+pub(super) const STRIPS: u32 = 8;
+
+// Shared with the grid and the noodles
+pub(super) const LINE_COLOR: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const CROSSING_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+const NOT_CROSSING_COLOR: Rgba<u8> = Rgba([0, 160, 0, 255]);
+
+/// Needles dropped on parallel lines, one needle per sample.
+///
+/// Needles crossing a line are red, the others green.
+pub struct NeedleScene {
+    estimator: BuffonsNeedle,
+    layout: Layout,
+    // Saves previous frames, needles are drawn onto it as they land
+    canvas: RgbaImage,
+    // Draws which part of the canvas each of the needles lands on
+    placement: SeededRng,
+}
+
+impl NeedleScene {
+    pub fn new(estimator: BuffonsNeedle, layout: Layout) -> Self {
+        let mut canvas = RgbaImage::from_pixel(
+            layout.canvas_width(),
+            layout.canvas_height(),
+            BACKGROUND_COLOR,
+        );
+        draw_parallel_lines(&mut canvas, LINE_COLOR);
+        NeedleScene {
+            estimator,
+            layout,
+            canvas,
+            placement: placement_rng(0),
+        }
+    }
+
+    /// Place the needles on the canvas with numbers drawn from `seed`, apart
+    /// from those of the samples.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.placement = placement_rng(seed);
+        self
+    }
+
+    pub fn estimator(&self) -> &BuffonsNeedle {
+        &self.estimator
+    }
+}
+
+impl Scene for NeedleScene {
+    fn title(&self) -> &'static str {
+        "Approximating Pi - Buffon's needle"
+    }
+
+    fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    fn resize(&mut self, layout: Layout) {
+        stretch_drops(&mut self.canvas, layout, draw_parallel_lines);
+        self.layout = layout;
+    }
+
+    fn reset(&mut self) {
+        self.estimator.reset();
+        let placement = self.placement.clone();
+        *self = NeedleScene::new(self.estimator.clone(), self.layout);
+        self.placement = placement;
+    }
+
+    fn advance(&mut self, rng: &mut dyn RngCore, samples: u64) {
+        let mut needles = Vec::new();
+        self.estimator
+            .sample_with(rng, samples, |needle| needles.push(needle));
+
+        // Pixels per unit of length, so that STRIPS gaps fit across the canvas
+        let scale =
+            self.layout.canvas_width() as f64 / (STRIPS as f64 * self.estimator.parallel_width());
+        for needle in needles {
+            // The estimator only decides where the needle lies across its strip,
+            // which strip and how far down the canvas is up to the visuals
+            let strip = self.placement.gen_range(0, STRIPS) as f64;
+            let start_y = self
+                .placement
+                .gen_range(0.0, self.layout.canvas_height() as f64);
+            draw_needle(&mut self.canvas, needle, strip, start_y, scale);
+        }
+    }
+
+    fn render(&self, frame: &mut RgbaImage) {
+        imageops::replace(frame, &self.canvas, 0, 0);
+    }
+
+    fn estimate(&self) -> Estimate {
+        self.estimator.estimate()
+    }
+
+    fn caption(&self) -> String {
+        format!(
+            "{}   crossed {} / {}",
+            self.estimator.estimate(),
+            self.estimator.crossed_needles(),
+            self.estimator.sample_count()
+        )
+    }
+
+    fn caption_size(&self) -> u32 {
+        22
+    }
+}
+
+// Stretch what was dropped so far over a canvas the size of `layout`, rubbing
+// out the lines drawn by `draw_lines` first so that they are drawn again sharp
+pub(super) fn stretch_drops<F>(canvas: &mut RgbaImage, layout: Layout, draw_lines: F)
+where
+    F: Fn(&mut RgbaImage, Rgba<u8>),
+{
+    draw_lines(canvas, BACKGROUND_COLOR);
+    *canvas = imageops::resize(
+        canvas,
+        layout.canvas_width(),
+        layout.canvas_height(),
+        FilterType::Nearest,
+    );
+    draw_lines(canvas, LINE_COLOR);
+}
+
+// Red for whatever crosses a line, green otherwise
+pub(super) fn crossing_color(crosses: bool) -> Rgba<u8> {
+    if crosses {
+        CROSSING_COLOR
+    } else {
+        NOT_CROSSING_COLOR
+    }
+}
+
+pub(super) fn draw_parallel_lines(canvas: &mut RgbaImage, color: Rgba<u8>) {
+    let (width, height) = canvas.dimensions();
+    for i in 0..=STRIPS {
+        // Keep the last line on the canvas
+        let x = (i * width / STRIPS).min(width - 1) as f64;
+        draw_line(canvas, [x, 0.0], [x, height as f64], color);
+    }
+}
+
+fn draw_needle(canvas: &mut RgbaImage, needle: Needle, strip: f64, start_y: f64, scale: f64) {
+    let strip_width = canvas.width() as f64 / STRIPS as f64;
+    let start = [strip * strip_width + needle.start_x * scale, start_y];
+    let end = [
+        strip * strip_width + needle.end_x * scale,
+        start_y - needle.end_y * scale,
+    ];
+    draw_line(canvas, start, end, crossing_color(needle.crosses));
+}
@@ -0,0 +1,133 @@
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+use rand::RngCore;
+
+use super::draw::{fill_ellipse, fill_rect};
+use super::{Layout, Scene, BACKGROUND_COLOR};
+use crate::circle::{CircleInSquare, Dart};
+use crate::estimator::{Estimate, PiEstimator};
+use crate::map;
+
+const CIRCLE_COLOR: Rgba<u8> = Rgba([0, 255, 0, 255]);
+pub const HIT_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+pub const MISS_COLOR: Rgba<u8> = Rgba([0, 0, 255, 255]);
+// Darts are drawn as squares of this many pixels a side
+const DART_SIZE: f64 = 5.0;
+
+/// Darts thrown at a circle inside a square, one dart per sample.
+///
+/// Darts landing inside the circle are drawn in `hit_color` (red by default),
+/// the others in `miss_color` (blue by default).
+pub struct CircleScene {
+    estimator: CircleInSquare,
+    layout: Layout,
+    hit_color: Rgba<u8>,
+    miss_color: Rgba<u8>,
+    // Saves previous frames, darts are drawn onto it as they land
+    canvas: RgbaImage,
+}
+
+impl CircleScene {
+    pub fn new(estimator: CircleInSquare, layout: Layout) -> Self {
+        CircleScene {
+            estimator,
+            layout,
+            hit_color: HIT_COLOR,
+            miss_color: MISS_COLOR,
+            canvas: blank_canvas(layout),
+        }
+    }
+
+    /// Draw the darts inside the circle in `hit_color` and the others in `miss_color`.
+    pub fn with_colors(mut self, hit_color: Rgba<u8>, miss_color: Rgba<u8>) -> Self {
+        self.hit_color = hit_color;
+        self.miss_color = miss_color;
+        self
+    }
+
+    pub fn estimator(&self) -> &CircleInSquare {
+        &self.estimator
+    }
+}
+
+impl Scene for CircleScene {
+    fn title(&self) -> &'static str {
+        "Approximating Pi"
+    }
+
+    fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    fn resize(&mut self, layout: Layout) {
+        // Scale the darts thrown so far from the old square onto the new one
+        let [x, y, side, _] = self.layout.square();
+        let old = imageops::crop_imm(&self.canvas, x as u32, y as u32, side as u32, side as u32);
+        let [x, y, side, _] = layout.square();
+        let darts = imageops::resize(
+            &old.to_image(),
+            side as u32,
+            side as u32,
+            FilterType::Nearest,
+        );
+
+        self.canvas = blank_canvas(layout);
+        imageops::replace(&mut self.canvas, &darts, x as u32, y as u32);
+        self.layout = layout;
+    }
+
+    fn reset(&mut self) {
+        self.estimator.reset();
+        self.canvas = blank_canvas(self.layout);
+    }
+
+    fn advance(&mut self, rng: &mut dyn RngCore, samples: u64) {
+        // Every dart lands on the canvas, which is uploaded once per frame however many there are
+        let (canvas, square) = (&mut self.canvas, self.layout.square());
+        let colors = (self.hit_color, self.miss_color);
+        self.estimator.sample_with(rng, samples, |dart| {
+            let color = if dart.inside { colors.0 } else { colors.1 };
+            draw_dart(canvas, square, dart, color)
+        });
+    }
+
+    fn render(&self, frame: &mut RgbaImage) {
+        imageops::replace(frame, &self.canvas, 0, 0);
+    }
+
+    fn estimate(&self) -> Estimate {
+        self.estimator.estimate()
+    }
+
+    fn caption(&self) -> String {
+        format!(
+            "{}   inside {} / {}",
+            self.estimator.estimate(),
+            self.estimator.inside_count(),
+            self.estimator.sample_count()
+        )
+    }
+
+    fn caption_size(&self) -> u32 {
+        22
+    }
+}
+
+// The circle fills the square in the middle of the canvas
+fn blank_canvas(layout: Layout) -> RgbaImage {
+    let mut canvas = RgbaImage::from_pixel(
+        layout.canvas_width(),
+        layout.canvas_height(),
+        BACKGROUND_COLOR,
+    );
+    fill_ellipse(&mut canvas, layout.square(), CIRCLE_COLOR);
+    canvas
+}
+
+fn draw_dart(canvas: &mut RgbaImage, square: [f64; 4], dart: Dart, color: Rgba<u8>) {
+    // Only quantise the dart to a pixel now that it's being plotted
+    let [x, y, side, _] = square;
+    let pos_x = map(dart.x, -1.0, 1.0, x, x + side).floor();
+    let pos_y = map(dart.y, -1.0, 1.0, y, y + side).floor();
+    fill_rect(canvas, [pos_x, pos_y, DART_SIZE, DART_SIZE], color);
+}
@@ -0,0 +1,84 @@
+// Drawing primitives for the software renderer
+
+use image::{Rgba, RgbaImage};
+use rusttype::{point, Font, Scale};
+
+// Same font as the piston windows
+static FONT_DATA: &[u8] = include_bytes!("../../assets/FiraSans-Regular.ttf");
+
+/// Put a pixel into `canvas`, skipping it if it falls outside.
+pub fn put_pixel(canvas: &mut RgbaImage, x: f64, y: f64, color: Rgba<u8>) {
+    if x >= 0.0 && y >= 0.0 && (x as u32) < canvas.width() && (y as u32) < canvas.height() {
+        canvas.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+/// Put a line of pixels from `from` to `to` into `canvas`.
+pub fn draw_line(canvas: &mut RgbaImage, from: [f64; 2], to: [f64; 2], color: Rgba<u8>) {
+    let steps = (to[0] - from[0]).abs().max((to[1] - from[1]).abs()).ceil() as u32;
+    for i in 0..=steps {
+        let t = if steps == 0 {
+            0.0
+        } else {
+            i as f64 / steps as f64
+        };
+        let x = from[0] + t * (to[0] - from[0]);
+        let y = from[1] + t * (to[1] - from[1]);
+        put_pixel(canvas, x, y, color);
+    }
+}
+
+/// Fill the rectangle `[x, y, width, height]`.
+pub fn fill_rect(canvas: &mut RgbaImage, rect: [f64; 4], color: Rgba<u8>) {
+    let [x, y, width, height] = rect;
+    for j in y.max(0.0).round() as u32..(y + height).max(0.0).round() as u32 {
+        for i in x.max(0.0).round() as u32..(x + width).max(0.0).round() as u32 {
+            put_pixel(canvas, i as f64, j as f64, color);
+        }
+    }
+}
+
+/// Fill the ellipse inscribed in the rectangle `[x, y, width, height]`.
+pub fn fill_ellipse(canvas: &mut RgbaImage, rect: [f64; 4], color: Rgba<u8>) {
+    let [x, y, width, height] = rect;
+    let (center_x, center_y) = (x + width / 2.0, y + height / 2.0);
+    for j in y.max(0.0) as u32..(y + height).max(0.0).ceil() as u32 {
+        for i in x.max(0.0) as u32..(x + width).max(0.0).ceil() as u32 {
+            // Test the middle of the pixel
+            let dx = (i as f64 + 0.5 - center_x) / (width / 2.0);
+            let dy = (j as f64 + 0.5 - center_y) / (height / 2.0);
+            if dx * dx + dy * dy <= 1.0 {
+                put_pixel(canvas, i as f64, j as f64, color);
+            }
+        }
+    }
+}
+
+/// Draw `text` with its baseline starting at `position`, `size` pixels high.
+pub fn draw_text(
+    canvas: &mut RgbaImage,
+    text: &str,
+    position: [f64; 2],
+    size: u32,
+    color: Rgba<u8>,
+) {
+    let font = Font::from_bytes(FONT_DATA).expect("bundled font is valid");
+    let start = point(position[0] as f32, position[1] as f32);
+    for glyph in font.layout(text, Scale::uniform(size as f32), start) {
+        if let Some(bounds) = glyph.pixel_bounding_box() {
+            glyph.draw(|x, y, coverage| {
+                let x = x as i32 + bounds.min.x;
+                let y = y as i32 + bounds.min.y;
+                if x < 0 || y < 0 || x as u32 >= canvas.width() || y as u32 >= canvas.height() {
+                    return;
+                }
+                // Blend the glyph's coverage over what's already there
+                let pixel = canvas.get_pixel_mut(x as u32, y as u32);
+                for channel in 0..3 {
+                    let under = pixel[channel] as f32;
+                    pixel[channel] = (under + (color[channel] as f32 - under) * coverage) as u8;
+                }
+            });
+        }
+    }
+}
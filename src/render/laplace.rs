@@ -0,0 +1,156 @@
+use image::imageops;
+use image::{Rgba, RgbaImage};
+use rand::{Rng, RngCore};
+
+use super::buffon::{crossing_color, stretch_drops, LINE_COLOR};
+use super::draw::draw_line;
+use super::{placement_rng, Layout, Scene, BACKGROUND_COLOR};
+use crate::estimator::{Estimate, PiEstimator};
+use crate::laplace::{BuffonLaplace, GridNeedle};
+use crate::rng::SeededRng;
+
+// Number of grid cells across the canvas
+const COLUMNS: u32 = 8;
+
+/// Needles dropped on a grid, one needle per sample.
+///
+/// Needles crossing a line are red, the others green.
+pub struct GridScene {
+    estimator: BuffonLaplace,
+    layout: Layout,
+    // Saves previous frames, needles are drawn onto it as they land
+    canvas: RgbaImage,
+    // Draws which part of the canvas each of the needles lands on
+    placement: SeededRng,
+}
+
+impl GridScene {
+    pub fn new(estimator: BuffonLaplace, layout: Layout) -> Self {
+        let mut canvas = RgbaImage::from_pixel(
+            layout.canvas_width(),
+            layout.canvas_height(),
+            BACKGROUND_COLOR,
+        );
+        draw_grid(&mut canvas, &estimator, LINE_COLOR);
+        GridScene {
+            estimator,
+            layout,
+            canvas,
+            placement: placement_rng(0),
+        }
+    }
+
+    /// Place the needles on the canvas with numbers drawn from `seed`, apart
+    /// from those of the samples.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.placement = placement_rng(seed);
+        self
+    }
+
+    pub fn estimator(&self) -> &BuffonLaplace {
+        &self.estimator
+    }
+
+    // Pixels per unit of length, so that COLUMNS cells fit across the canvas
+    fn scale(&self) -> f64 {
+        self.layout.canvas_width() as f64 / (COLUMNS as f64 * self.estimator.cell_width())
+    }
+}
+
+impl Scene for GridScene {
+    fn title(&self) -> &'static str {
+        "Approximating Pi - Buffon-Laplace grid"
+    }
+
+    fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    fn resize(&mut self, layout: Layout) {
+        let estimator = &self.estimator;
+        stretch_drops(&mut self.canvas, layout, |canvas, color| {
+            draw_grid(canvas, estimator, color)
+        });
+        self.layout = layout;
+    }
+
+    fn reset(&mut self) {
+        self.estimator.reset();
+        let placement = self.placement.clone();
+        *self = GridScene::new(self.estimator.clone(), self.layout);
+        self.placement = placement;
+    }
+
+    fn advance(&mut self, rng: &mut dyn RngCore, samples: u64) {
+        let mut needles = Vec::new();
+        self.estimator
+            .sample_with(rng, samples, |needle| needles.push(needle));
+
+        let scale = self.scale();
+        let cell_height = self.estimator.cell_height() * scale;
+        let rows = (self.layout.canvas_height() as f64 / cell_height).ceil() as u32;
+        for needle in needles {
+            // Like the needles on parallel lines, any cell of the canvas
+            let cell = [
+                self.placement.gen_range(0, COLUMNS) as f64 * self.layout.canvas_width() as f64
+                    / COLUMNS as f64,
+                self.placement.gen_range(0, rows.max(1)) as f64 * cell_height,
+            ];
+            draw_needle(&mut self.canvas, needle, cell, scale);
+        }
+    }
+
+    fn render(&self, frame: &mut RgbaImage) {
+        imageops::replace(frame, &self.canvas, 0, 0);
+    }
+
+    fn estimate(&self) -> Estimate {
+        self.estimator.estimate()
+    }
+
+    fn caption(&self) -> String {
+        format!(
+            "{}   crossed {} / {}",
+            self.estimator.estimate(),
+            self.estimator.crossings(),
+            self.estimator.sample_count()
+        )
+    }
+
+    fn caption_size(&self) -> u32 {
+        22
+    }
+}
+
+// Vertical lines COLUMNS cells apart across the canvas, horizontal lines as far
+// apart as the cells are high, counted from the bottom
+fn draw_grid(canvas: &mut RgbaImage, estimator: &BuffonLaplace, color: Rgba<u8>) {
+    let (width, height) = canvas.dimensions();
+    for i in 0..=COLUMNS {
+        // Keep the last line on the canvas
+        let x = (i * width / COLUMNS).min(width - 1) as f64;
+        draw_line(canvas, [x, 0.0], [x, height as f64], color);
+    }
+    let cell_height =
+        width as f64 * estimator.cell_height() / (COLUMNS as f64 * estimator.cell_width());
+    let mut y = 0_f64;
+    while y < height as f64 {
+        let row = (height as f64 - 1.0 - y).max(0.0);
+        draw_line(canvas, [0.0, row], [width as f64, row], color);
+        y += cell_height;
+    }
+}
+
+// `cell` is the bottom left corner of the needle's cell, in pixels from the bottom left of the canvas
+fn draw_needle(canvas: &mut RgbaImage, needle: GridNeedle, cell: [f64; 2], scale: f64) {
+    let bottom = canvas.height() as f64 - 1.0;
+    let start = [
+        cell[0] + needle.start_x * scale,
+        bottom - (cell[1] + needle.start_y * scale),
+    ];
+    let end = [
+        cell[0] + needle.end_x * scale,
+        bottom - (cell[1] + needle.end_y * scale),
+    ];
+    draw_line(canvas, start, end, crossing_color(needle.crosses));
+}
@@ -0,0 +1,271 @@
+//! Software rendering of the visuals into `image` buffers
+//!
+//! Each visualised method is a [`Scene`]: it advances one animation frame at a
+//! time and renders itself into an [`RgbaImage`]. The piston windows show these
+//! frames, and [`write_png_frames`] / [`write_gif`] save them without a display.
+//! A [`Pacer`] decides how many samples each frame draws.
+
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+use std::str::FromStr;
+
+use image::gif::GifEncoder;
+use image::{Delay, Frame, ImageResult, Rgba, RgbaImage};
+use rand::RngCore;
+
+use crate::estimator::Estimate;
+use crate::rng::{self, SeededRng};
+
+pub mod buffon;
+pub mod circle;
+pub mod draw;
+pub mod laplace;
+pub mod noodle;
+pub mod pace;
+pub mod random_walk;
+
+pub use buffon::NeedleScene;
+pub use circle::CircleScene;
+pub use laplace::GridScene;
+pub use noodle::NoodleScene;
+pub use pace::{Pacer, Rate};
+pub use random_walk::WalkScene;
+
+// Every visual is a canvas with a line of text below it, by default a 512 x 512 square
+pub const WIDTH: u32 = 512;
+pub const HEIGHT: u32 = 540;
+pub const TEXT_HEIGHT: u32 = 28;
+pub const TEXT_SIZE: u32 = 28;
+// Smallest canvas side, so that a tiny window still has something to draw on
+pub const MIN_CANVAS_SIZE: u32 = 64;
+
+pub const BACKGROUND_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+pub const TEXT_COLOR: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+// Where the needles, the grid and the noodles place each drop on the canvas,
+// apart from the samples
+fn placement_rng(seed: u64) -> SeededRng {
+    rng::seeded_stream(seed, rng::PLACEMENT_STREAM)
+}
+
+/// Size of a visual and where its parts go.
+///
+/// The canvas fills the width and everything above the line of text at the bottom.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Layout {
+    /// Layout of a `width` x `height` visual, grown to fit `MIN_CANVAS_SIZE` if needed.
+    pub fn new(width: u32, height: u32) -> Self {
+        Layout {
+            width: width.max(MIN_CANVAS_SIZE),
+            height: height.max(MIN_CANVAS_SIZE + TEXT_HEIGHT),
+        }
+    }
+
+    pub fn canvas_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn canvas_height(&self) -> u32 {
+        self.height - TEXT_HEIGHT
+    }
+
+    /// The largest square centred in the canvas, as `[x, y, side, side]`.
+    pub fn square(&self) -> [f64; 4] {
+        let side = self.canvas_width().min(self.canvas_height());
+        let x = (self.canvas_width() - side) / 2;
+        let y = (self.canvas_height() - side) / 2;
+        [x as f64, y as f64, side as f64, side as f64]
+    }
+
+    /// Start of the caption's baseline.
+    pub fn caption_position(&self) -> [f64; 2] {
+        [10.0, (self.height - 5) as f64]
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::new(WIDTH, HEIGHT)
+    }
+}
+
+// Parses `<width>x<height>`, e.g. `800x600`
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{}' is not a size like 800x600", s);
+        let (width, height) = s.split_once(['x', 'X']).ok_or_else(invalid)?;
+        let width = width.trim().parse().map_err(|_| invalid())?;
+        let height = height.trim().parse().map_err(|_| invalid())?;
+        Ok(Layout::new(width, height))
+    }
+}
+
+/// Parse a colour written as hex `RRGGBB`, with or without a leading `#`.
+pub fn parse_color(hex: &str) -> Result<Rgba<u8>, String> {
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    let invalid = || format!("'{}' is not a colour like ff8000", hex);
+    if digits.len() != 6 || !digits.is_ascii() {
+        return Err(invalid());
+    }
+    let mut color = [0, 0, 0, 255];
+    for (i, channel) in color.iter_mut().take(3).enumerate() {
+        *channel = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(Rgba(color))
+}
+
+/// An animated visual of one method.
+pub trait Scene {
+    /// Window title.
+    fn title(&self) -> &'static str;
+
+    /// Current size of the visual.
+    fn layout(&self) -> Layout;
+
+    /// Lay the visual out again for a new size, e.g. when its window is resized.
+    fn resize(&mut self, layout: Layout);
+
+    /// Start again with no samples drawn.
+    fn reset(&mut self);
+
+    /// Move the animation on by one frame, drawing `samples` more samples from `rng`.
+    fn advance(&mut self, rng: &mut dyn RngCore, samples: u64);
+
+    /// Rate the scene is meant to be played at.
+    fn default_rate(&self) -> Rate {
+        Rate::PerFrame(1.0)
+    }
+
+    /// Draw the current frame, except for the caption, into `frame` of the size of `layout()`.
+    fn render(&self, frame: &mut RgbaImage);
+
+    /// Text shown below the canvas, e.g. the current approximation of pi.
+    fn caption(&self) -> String;
+
+    /// Current approximation of pi from the samples drawn so far.
+    fn estimate(&self) -> Estimate;
+
+    /// Font size of the caption, smaller for longer captions.
+    fn caption_size(&self) -> u32 {
+        TEXT_SIZE
+    }
+}
+
+/// Render the current frame of `scene` with its caption.
+pub fn render_frame(scene: &dyn Scene) -> RgbaImage {
+    let layout = scene.layout();
+    let mut frame = RgbaImage::from_pixel(layout.width, layout.height, BACKGROUND_COLOR);
+    scene.render(&mut frame);
+    draw::draw_text(
+        &mut frame,
+        &scene.caption(),
+        layout.caption_position(),
+        scene.caption_size(),
+        TEXT_COLOR,
+    );
+    frame
+}
+
+/// Advance `scene` `frames` times, saving `frame_00000.png`, `frame_00001.png`, ... into `dir`.
+///
+/// The scene is advanced `advance_per_frame` times between saved frames, at
+/// `rate` with each frame taken to last `FRAME_SECONDS`.
+pub fn write_png_frames(
+    scene: &mut dyn Scene,
+    rng: &mut dyn RngCore,
+    frames: u32,
+    advance_per_frame: u32,
+    rate: Rate,
+    dir: &Path,
+) -> ImageResult<()> {
+    fs::create_dir_all(dir)?;
+    let mut pacer = Pacer::new(rate);
+    for i in 0..frames {
+        for _ in 0..advance_per_frame {
+            scene.advance(rng, pacer.samples(pace::FRAME_SECONDS));
+        }
+        render_frame(scene).save(dir.join(format!("frame_{:05}.png", i)))?;
+    }
+    Ok(())
+}
+
+/// Advance `scene` `frames` times, saving every frame into the animated GIF at `path`.
+///
+/// The scene is advanced `advance_per_frame` times between saved frames, at
+/// `rate`, and each saved frame is shown for `delay_ms` milliseconds.
+pub fn write_gif(
+    scene: &mut dyn Scene,
+    rng: &mut dyn RngCore,
+    frames: u32,
+    advance_per_frame: u32,
+    rate: Rate,
+    delay_ms: u32,
+    path: &Path,
+) -> ImageResult<()> {
+    let mut encoder = GifEncoder::new(BufWriter::new(fs::File::create(path)?));
+    let mut pacer = Pacer::new(rate);
+    // The frames in between saved frames share its delay
+    let frame_seconds = delay_ms as f64 / 1000.0 / advance_per_frame.max(1) as f64;
+    for _ in 0..frames {
+        for _ in 0..advance_per_frame {
+            scene.advance(rng, pacer.samples(frame_seconds));
+        }
+        let delay = Delay::from_numer_denom_ms(delay_ms, 1);
+        encoder.encode_frame(Frame::from_parts(render_frame(scene), 0, 0, delay))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_parses_sizes() {
+        assert_eq!("800x600".parse(), Ok(Layout::new(800, 600)));
+        assert_eq!(" 640 X 480 ".parse(), Ok(Layout::new(640, 480)));
+        let layout: Layout = "800x600".parse().unwrap();
+        assert_eq!(layout.canvas_height(), 600 - TEXT_HEIGHT);
+    }
+
+    #[test]
+    fn layout_grows_tiny_sizes() {
+        let layout: Layout = "1x1".parse().unwrap();
+        assert_eq!(layout.canvas_width(), MIN_CANVAS_SIZE);
+        assert_eq!(layout.canvas_height(), MIN_CANVAS_SIZE);
+    }
+
+    #[test]
+    fn layout_rejects_other_text() {
+        for size in ["800", "800x", "x600", "800x-1", "axb", ""] {
+            assert_eq!(
+                size.parse::<Layout>(),
+                Err(format!("'{}' is not a size like 800x600", size))
+            );
+        }
+    }
+
+    #[test]
+    fn colors_parse_with_or_without_hash() {
+        assert_eq!(parse_color("ff8000"), Ok(Rgba([255, 128, 0, 255])));
+        assert_eq!(parse_color("#00A0ff"), Ok(Rgba([0, 160, 255, 255])));
+    }
+
+    #[test]
+    fn colors_reject_other_text() {
+        for hex in ["", "#", "fff", "ff80000", "gg0000", "#ff 000", "ff80é"] {
+            assert_eq!(
+                parse_color(hex),
+                Err(format!("'{}' is not a colour like ff8000", hex))
+            );
+        }
+    }
+}
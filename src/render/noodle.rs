@@ -0,0 +1,119 @@
+use image::imageops;
+use image::RgbaImage;
+use rand::{Rng, RngCore};
+
+use super::buffon::{crossing_color, draw_parallel_lines, stretch_drops, LINE_COLOR, STRIPS};
+use super::draw::draw_line;
+use super::{placement_rng, Layout, Scene, BACKGROUND_COLOR};
+use crate::estimator::{Estimate, PiEstimator};
+use crate::noodle::BuffonsNoodle;
+use crate::rng::SeededRng;
+
+/// Noodles dropped on parallel lines, one noodle per sample.
+///
+/// Noodles crossing a line are red, the others green.
+pub struct NoodleScene {
+    estimator: BuffonsNoodle,
+    layout: Layout,
+    // Saves previous frames, noodles are drawn onto it as they land
+    canvas: RgbaImage,
+    // Draws which part of the canvas each of the noodles lands on
+    placement: SeededRng,
+}
+
+impl NoodleScene {
+    pub fn new(estimator: BuffonsNoodle, layout: Layout) -> Self {
+        let mut canvas = RgbaImage::from_pixel(
+            layout.canvas_width(),
+            layout.canvas_height(),
+            BACKGROUND_COLOR,
+        );
+        draw_parallel_lines(&mut canvas, LINE_COLOR);
+        NoodleScene {
+            estimator,
+            layout,
+            canvas,
+            placement: placement_rng(0),
+        }
+    }
+
+    /// Place the noodles on the canvas with numbers drawn from `seed`, apart
+    /// from those of the samples.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.placement = placement_rng(seed);
+        self
+    }
+
+    pub fn estimator(&self) -> &BuffonsNoodle {
+        &self.estimator
+    }
+}
+
+impl Scene for NoodleScene {
+    fn title(&self) -> &'static str {
+        "Approximating Pi - Buffon's noodle"
+    }
+
+    fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    fn resize(&mut self, layout: Layout) {
+        stretch_drops(&mut self.canvas, layout, draw_parallel_lines);
+        self.layout = layout;
+    }
+
+    fn reset(&mut self) {
+        self.estimator.reset();
+        let placement = self.placement.clone();
+        *self = NoodleScene::new(self.estimator.clone(), self.layout);
+        self.placement = placement;
+    }
+
+    fn advance(&mut self, rng: &mut dyn RngCore, samples: u64) {
+        let mut noodles = Vec::new();
+        self.estimator
+            .sample_with(rng, samples, |vertices, crossings| {
+                noodles.push((vertices.to_vec(), crossings))
+            });
+
+        // Pixels per unit of length, so that STRIPS gaps fit across the canvas
+        let scale =
+            self.layout.canvas_width() as f64 / (STRIPS as f64 * self.estimator.parallel_width());
+        let strip_width = self.layout.canvas_width() as f64 / STRIPS as f64;
+        for (vertices, crossings) in noodles {
+            // Like the needles, any strip and anywhere down the canvas
+            let left = self.placement.gen_range(0, STRIPS) as f64 * strip_width;
+            let middle_y = self
+                .placement
+                .gen_range(0.0, self.layout.canvas_height() as f64);
+            let color = crossing_color(crossings > 0);
+            for piece in vertices.windows(2) {
+                let [from, to] =
+                    [piece[0], piece[1]].map(|[x, y]| [left + x * scale, middle_y - y * scale]);
+                draw_line(&mut self.canvas, from, to, color);
+            }
+        }
+    }
+
+    fn render(&self, frame: &mut RgbaImage) {
+        imageops::replace(frame, &self.canvas, 0, 0);
+    }
+
+    fn estimate(&self) -> Estimate {
+        self.estimator.estimate()
+    }
+
+    fn caption(&self) -> String {
+        format!(
+            "{}   {} crossings / {}",
+            self.estimator.estimate(),
+            self.estimator.crossings(),
+            self.estimator.sample_count()
+        )
+    }
+
+    fn caption_size(&self) -> u32 {
+        22
+    }
+}
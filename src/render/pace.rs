@@ -0,0 +1,134 @@
+// How many samples the visuals draw each frame
+
+use std::fmt;
+use std::str::FromStr;
+
+// Nominal length of a frame when there is no window to time, e.g. for PNG frames
+pub const FRAME_SECONDS: f64 = 1.0 / 60.0;
+// Longest frame a per second rate catches up on, so a stalled window doesn't dump a backlog at once
+const MAX_FRAME_SECONDS: f64 = 0.25;
+
+/// Speed at which a scene draws samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rate {
+    /// Samples drawn each frame.
+    PerFrame(f64),
+    /// Samples drawn each second, however many frames that is.
+    PerSecond(f64),
+}
+
+impl Rate {
+    /// The same kind of rate, `factor` times as fast.
+    pub fn scaled(self, factor: f64) -> Rate {
+        match self {
+            Rate::PerFrame(rate) => Rate::PerFrame(rate * factor),
+            Rate::PerSecond(rate) => Rate::PerSecond(rate * factor),
+        }
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rate::PerFrame(rate) => write!(f, "{} per frame", rate),
+            Rate::PerSecond(rate) => write!(f, "{} per second", rate),
+        }
+    }
+}
+
+// Parses `<N>` for samples per frame or `<N>/s` for samples per second
+impl FromStr for Rate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, per_second) = match s.strip_suffix("/s") {
+            Some(number) => (number, true),
+            None => (s, false),
+        };
+        let rate: f64 = number
+            .trim()
+            .parse()
+            .map_err(|_| format!("'{}' is not a rate like 100 or 5000/s", s))?;
+        if !rate.is_finite() || rate <= 0.0 {
+            return Err(format!("rate '{}' must be a positive number", s));
+        }
+        Ok(if per_second {
+            Rate::PerSecond(rate)
+        } else {
+            Rate::PerFrame(rate)
+        })
+    }
+}
+
+/// Turns a [`Rate`] into a whole number of samples for each frame.
+///
+/// Fractions of a sample are carried over to the next frame, so a rate of 0.5
+/// per frame draws a sample every other frame.
+#[derive(Debug, Clone)]
+pub struct Pacer {
+    rate: Rate,
+    owed: f64,
+}
+
+impl Pacer {
+    pub fn new(rate: Rate) -> Self {
+        Pacer { rate, owed: 0.0 }
+    }
+
+    pub fn rate(&self) -> Rate {
+        self.rate
+    }
+
+    pub fn set_rate(&mut self, rate: Rate) {
+        self.rate = rate;
+    }
+
+    /// Number of samples to draw in a frame that took `seconds`.
+    pub fn samples(&mut self, seconds: f64) -> u64 {
+        self.owed += match self.rate {
+            Rate::PerFrame(rate) => rate,
+            Rate::PerSecond(rate) => rate * seconds.clamp(0.0, MAX_FRAME_SECONDS),
+        };
+        let samples = self.owed.floor();
+        self.owed -= samples;
+        samples as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_parses_per_frame_and_per_second() {
+        assert_eq!("100".parse(), Ok(Rate::PerFrame(100.0)));
+        assert_eq!("0.5".parse(), Ok(Rate::PerFrame(0.5)));
+        assert_eq!("5000/s".parse(), Ok(Rate::PerSecond(5000.0)));
+    }
+
+    #[test]
+    fn rate_rejects_other_text() {
+        assert_eq!(
+            "fast".parse::<Rate>(),
+            Err("'fast' is not a rate like 100 or 5000/s".to_string())
+        );
+        assert!("5000/m".parse::<Rate>().is_err());
+        for rate in ["0", "-1", "inf/s", "NaN"] {
+            assert_eq!(
+                rate.parse::<Rate>(),
+                Err(format!("rate '{}' must be a positive number", rate))
+            );
+        }
+    }
+
+    #[test]
+    fn pacer_carries_fractions_over() {
+        let mut pacer = Pacer::new(Rate::PerFrame(0.5));
+        let samples: Vec<u64> = (0..4).map(|_| pacer.samples(FRAME_SECONDS)).collect();
+        assert_eq!(samples, [0, 1, 0, 1]);
+
+        // A stalled frame only catches up on MAX_FRAME_SECONDS
+        let mut pacer = Pacer::new(Rate::PerSecond(100.0));
+        assert_eq!(pacer.samples(10.0), 25);
+    }
+}
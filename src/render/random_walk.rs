@@ -0,0 +1,229 @@
+use image::{Rgba, RgbaImage};
+use rand::RngCore;
+
+use super::draw::{draw_line, fill_rect};
+use super::{Layout, Rate, Scene};
+use crate::estimator::{Estimate, PiEstimator};
+use crate::random_walk::RandomWalk;
+
+// Walks animated side by side
+const WALKS_PER_BATCH: usize = 8;
+// Frames it takes to animate one batch of walks at the default rate
+const FRAMES_PER_BATCH: u64 = 60;
+
+const AXIS_COLOR: Rgba<u8> = Rgba([153, 153, 153, 255]);
+const HISTOGRAM_COLOR: Rgba<u8> = Rgba([128, 128, 128, 255]);
+const MEAN_COLOR: Rgba<u8> = Rgba([0, 0, 255, 255]);
+const THEORY_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+const PATH_COLORS: [Rgba<u8>; 4] = [
+    Rgba([0, 153, 0, 255]),
+    Rgba([204, 102, 0, 255]),
+    Rgba([128, 0, 179, 255]),
+    Rgba([0, 153, 179, 255]),
+];
+
+/// Random walks animated a batch at a time, one step of the batch per sample.
+///
+/// The top half draws the paths with the running mean distance from the origin
+/// (blue) against the theoretical sqrt(2n / pi) (red). The bottom half is a
+/// histogram of the final distance of every walk so far.
+pub struct WalkScene {
+    estimator: RandomWalk,
+    layout: Layout,
+    // Show positions up to this distance from the origin
+    max_distance: f64,
+    // Paths of the batch being animated and how many of their steps are shown
+    paths: Vec<Vec<i64>>,
+    shown_steps: u64,
+    // Sum of |position| after each step, over every walk shown so far
+    sum_of_distances: Vec<f64>,
+    walks_shown: u64,
+    // Walks by final distance, one bin per possible distance
+    histogram: Vec<u64>,
+}
+
+impl WalkScene {
+    pub fn new(estimator: RandomWalk, layout: Layout) -> Self {
+        let steps = estimator.steps();
+        // 3 standard deviations (sqrt(steps)) from the origin
+        let max_distance = (3_f64 * (steps as f64).sqrt()).ceil().max(1_f64);
+        WalkScene {
+            estimator,
+            layout,
+            max_distance,
+            paths: Vec::new(),
+            shown_steps: steps,
+            sum_of_distances: vec![0_f64; steps as usize + 1],
+            walks_shown: 0,
+            histogram: vec![0_u64; max_distance as usize + 1],
+        }
+    }
+
+    pub fn estimator(&self) -> &RandomWalk {
+        &self.estimator
+    }
+
+    // The batch is fully drawn: count it and take the next one
+    fn next_batch(&mut self, rng: &mut dyn RngCore) {
+        let steps = self.estimator.steps();
+        for path in &self.paths {
+            for (step, position) in path.iter().enumerate() {
+                self.sum_of_distances[step] += position.abs() as f64;
+            }
+            let distance = path[path.len() - 1].unsigned_abs() as usize;
+            if distance < self.histogram.len() {
+                self.histogram[distance] += 1;
+            }
+        }
+        self.walks_shown += self.paths.len() as u64;
+
+        let mut paths = vec![vec![0]; WALKS_PER_BATCH];
+        let mut walk = 0;
+        self.estimator
+            .sample_with(rng, WALKS_PER_BATCH as u64, |step, position| {
+                paths[walk].push(position);
+                if step == steps {
+                    walk += 1;
+                }
+            });
+        self.paths = paths;
+        self.shown_steps = 0;
+    }
+
+    // The paths take the top half of the canvas, the histogram the bottom half
+    fn panel_height(&self) -> f64 {
+        (self.layout.canvas_height() / 2) as f64
+    }
+
+    // Position 0 runs across the middle of the top panel
+    fn to_y(&self, position: f64) -> f64 {
+        self.panel_height() / 2.0 * (1.0 - position / self.max_distance)
+    }
+}
+
+impl Scene for WalkScene {
+    fn title(&self) -> &'static str {
+        "Approximating Pi - random walk"
+    }
+
+    fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    fn resize(&mut self, layout: Layout) {
+        // Everything is drawn from scratch each frame
+        self.layout = layout;
+    }
+
+    fn reset(&mut self) {
+        self.estimator.reset();
+        *self = WalkScene::new(self.estimator.clone(), self.layout);
+    }
+
+    fn advance(&mut self, rng: &mut dyn RngCore, samples: u64) {
+        let steps = self.estimator.steps();
+        let mut remaining = samples;
+        while remaining > 0 {
+            if self.shown_steps >= steps {
+                self.next_batch(rng);
+            }
+            let shown = (steps - self.shown_steps).min(remaining);
+            self.shown_steps += shown;
+            remaining -= shown;
+        }
+    }
+
+    fn default_rate(&self) -> Rate {
+        let steps_per_frame = (self.estimator.steps() / FRAMES_PER_BATCH).max(1);
+        Rate::PerFrame(steps_per_frame as f64)
+    }
+
+    fn render(&self, frame: &mut RgbaImage) {
+        let steps = self.estimator.steps();
+        let width = self.layout.canvas_width() as f64;
+        let panel_height = self.panel_height();
+        let step_width = width / steps as f64;
+
+        draw_line(
+            frame,
+            [0.0, self.to_y(0.0)],
+            [width, self.to_y(0.0)],
+            AXIS_COLOR,
+        );
+        draw_line(
+            frame,
+            [0.0, panel_height],
+            [width, panel_height],
+            AXIS_COLOR,
+        );
+
+        for (i, path) in self.paths.iter().enumerate() {
+            let color = PATH_COLORS[i % PATH_COLORS.len()];
+            for step in 1..=self.shown_steps as usize {
+                let from = [
+                    (step - 1) as f64 * step_width,
+                    self.to_y(path[step - 1] as f64),
+                ];
+                let to = [step as f64 * step_width, self.to_y(path[step] as f64)];
+                draw_line(frame, from, to, color);
+            }
+        }
+
+        // Running mean distance against sqrt(2n / pi)
+        for step in 1..=steps as usize {
+            let x = [(step - 1) as f64 * step_width, step as f64 * step_width];
+            let theory = [
+                (2.0 * (step - 1) as f64 / std::f64::consts::PI).sqrt(),
+                (2.0 * step as f64 / std::f64::consts::PI).sqrt(),
+            ];
+            draw_line(
+                frame,
+                [x[0], self.to_y(theory[0])],
+                [x[1], self.to_y(theory[1])],
+                THEORY_COLOR,
+            );
+            if self.walks_shown > 0 {
+                let mean = [
+                    self.sum_of_distances[step - 1] / self.walks_shown as f64,
+                    self.sum_of_distances[step] / self.walks_shown as f64,
+                ];
+                draw_line(
+                    frame,
+                    [x[0], self.to_y(mean[0])],
+                    [x[1], self.to_y(mean[1])],
+                    MEAN_COLOR,
+                );
+            }
+        }
+
+        // Histogram of final distances, scaled to the tallest bar
+        let tallest = self.histogram.iter().copied().max().unwrap_or(0).max(1) as f64;
+        let bar_width = width / self.histogram.len() as f64;
+        for (distance, count) in self.histogram.iter().enumerate() {
+            let bar_height = (panel_height - 4.0) * *count as f64 / tallest;
+            let rect = [
+                distance as f64 * bar_width,
+                2.0 * panel_height - bar_height,
+                (bar_width - 1.0).max(1.0),
+                bar_height,
+            ];
+            fill_rect(frame, rect, HISTOGRAM_COLOR);
+        }
+    }
+
+    fn estimate(&self) -> Estimate {
+        self.estimator.estimate()
+    }
+
+    fn caption(&self) -> String {
+        format!(
+            "{}   walks {}",
+            self.estimator.estimate(),
+            self.estimator.sample_count()
+        )
+    }
+
+    fn caption_size(&self) -> u32 {
+        22
+    }
+}
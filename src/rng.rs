@@ -0,0 +1,29 @@
+use rand::SeedableRng;
+
+/// Random number generator used for reproducible runs.
+///
+/// ChaCha8 is a named, portable algorithm, so the same seed gives the same
+/// stream of numbers on every platform and every release of this crate.
+pub type SeededRng = rand_chacha::ChaCha8Rng;
+
+// Blocks of samples use the streams from 0 up, see ParallelSampler. These are
+// far enough apart that no run draws that many blocks.
+
+/// Stream of the random noodle polygon, so that its shape doesn't depend on the drops.
+pub const SHAPE_STREAM: u64 = u64::MAX;
+
+/// Stream of where the visuals place each drop on the canvas, so that the
+/// estimate doesn't depend on how many drops are drawn per frame.
+pub const PLACEMENT_STREAM: u64 = u64::MAX - 1;
+
+/// Create the random number generator for `seed`.
+pub fn seeded_rng(seed: u64) -> SeededRng {
+    SeededRng::seed_from_u64(seed)
+}
+
+/// Create the random number generator for `seed`, on `stream` instead of the first one.
+pub fn seeded_stream(seed: u64, stream: u64) -> SeededRng {
+    let mut rng = seeded_rng(seed);
+    rng.set_stream(stream);
+    rng
+}
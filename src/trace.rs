@@ -0,0 +1,240 @@
+use std::borrow::Cow;
+use std::f64::consts::PI;
+use std::io::{self, Write};
+
+use crate::estimator::PiEstimator;
+
+// Fewer samples than this are too noisy to fit how the error decays
+const MIN_FIT_SAMPLES: u64 = 100;
+
+/// The estimate after `samples` samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TracePoint {
+    pub samples: u64,
+    pub estimate: f64,
+    pub std_error: f64,
+}
+
+/// How the estimate of one method converges as samples accumulate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    pub method: String,
+    pub points: Vec<TracePoint>,
+}
+
+impl Trace {
+    /// Draw `total` samples into `estimator`, recording the estimate at
+    /// [`checkpoints`] spaced `per_decade` times per power of ten.
+    ///
+    /// `sample(estimator, n)` draws `n` more samples, e.g. with a
+    /// [`ParallelSampler`](crate::parallel::ParallelSampler).
+    pub fn record<E, F>(estimator: &mut E, total: u64, per_decade: u32, mut sample: F) -> Self
+    where
+        E: PiEstimator,
+        F: FnMut(&mut E, u64),
+    {
+        let mut points = Vec::new();
+        for checkpoint in checkpoints(total, per_decade) {
+            sample(estimator, checkpoint - estimator.sample_count());
+            let estimate = estimator.estimate();
+            points.push(TracePoint {
+                samples: estimate.samples,
+                estimate: estimate.value,
+                std_error: estimate.std_error,
+            });
+        }
+        Trace {
+            method: estimator.name(),
+            points,
+        }
+    }
+
+    /// Exponent `r` of the least squares fit `|estimate - pi| ~ C * n^r` over the checkpoints.
+    ///
+    /// Independent samples give about -0.5, quasi-random points can get close to -1.
+    /// The first checkpoints, below `MIN_FIT_SAMPLES`, are left out of the fit.
+    pub fn error_decay_rate(&self) -> f64 {
+        let logs: Vec<(f64, f64)> = self
+            .points
+            .iter()
+            .filter(|point| point.samples >= MIN_FIT_SAMPLES)
+            .map(|point| (point.samples as f64, (point.estimate - PI).abs()))
+            // An estimate that happens to hit pi exactly has no logarithm
+            .filter(|(_, error)| *error > 0.0 && error.is_finite())
+            .map(|(samples, error)| (samples.ln(), error.ln()))
+            .collect();
+        let n = logs.len() as f64;
+        let mean_x = logs.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = logs.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let covariance: f64 = logs.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+        let variance: f64 = logs.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        covariance / variance
+    }
+}
+
+/// Sample counts spaced evenly on a log scale, `per_decade` per power of ten, ending at `total`.
+pub fn checkpoints(total: u64, per_decade: u32) -> Vec<u64> {
+    let mut checkpoints: Vec<u64> = Vec::new();
+    let mut k = 0;
+    loop {
+        let checkpoint = 10_f64.powf(k as f64 / per_decade.max(1) as f64).round() as u64;
+        if checkpoint >= total {
+            break;
+        }
+        // Small counts round to the same integer, keep each one once
+        if checkpoints.last() != Some(&checkpoint) {
+            checkpoints.push(checkpoint);
+        }
+        k += 1;
+    }
+    if total > 0 {
+        checkpoints.push(total);
+    }
+    checkpoints
+}
+
+/// Write the traces as CSV with one row per checkpoint.
+pub fn write_csv<W: Write>(traces: &[Trace], mut writer: W) -> io::Result<()> {
+    writeln!(writer, "method,samples,estimate,std_error")?;
+    for trace in traces {
+        for point in &trace.points {
+            writeln!(
+                writer,
+                "{},{},{},{}",
+                csv_field(&trace.method),
+                point.samples,
+                point.estimate,
+                point.std_error
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the traces as a JSON array with one object per method.
+pub fn write_json<W: Write>(traces: &[Trace], mut writer: W) -> io::Result<()> {
+    writeln!(writer, "[")?;
+    for (i, trace) in traces.iter().enumerate() {
+        writeln!(writer, "  {{")?;
+        writeln!(writer, "    \"method\": \"{}\",", trace.method)?;
+        writeln!(writer, "    \"points\": [")?;
+        for (j, point) in trace.points.iter().enumerate() {
+            let separator = if j + 1 < trace.points.len() { "," } else { "" };
+            writeln!(
+                writer,
+                "      {{\"samples\": {}, \"estimate\": {}, \"std_error\": {}}}{}",
+                point.samples,
+                json_number(point.estimate),
+                json_number(point.std_error),
+                separator
+            )?;
+        }
+        writeln!(writer, "    ]")?;
+        let separator = if i + 1 < traces.len() { "," } else { "" };
+        writeln!(writer, "  }}{}", separator)?;
+    }
+    writeln!(writer, "]")
+}
+
+/// A CSV field, quoted as RFC 4180 asks when it holds a comma, a quote or a line
+/// break, e.g. the method `buffons needle (pi-free, sobol)`.
+pub fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// JSON has no representation for inf or NaN (e.g. no needles crossed a line yet).
+pub fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkpoints_are_distinct_and_end_at_the_total() {
+        assert_eq!(
+            checkpoints(1000, 4),
+            [1, 2, 3, 6, 10, 18, 32, 56, 100, 178, 316, 562, 1000]
+        );
+        // Ten per decade round to the same small counts, each is kept once
+        let small = checkpoints(30, 10);
+        assert!(
+            small.windows(2).all(|pair| pair[0] < pair[1]),
+            "{:?}",
+            small
+        );
+        assert_eq!(small.last(), Some(&30));
+        assert_eq!(checkpoints(1, 4), [1]);
+        assert!(checkpoints(0, 4).is_empty());
+    }
+
+    #[test]
+    fn json_writes_null_for_missing_errors() {
+        // A single sample has no standard error
+        let trace = Trace {
+            method: "circle".to_string(),
+            points: vec![TracePoint {
+                samples: 1,
+                estimate: 4.0,
+                std_error: f64::NAN,
+            }],
+        };
+        let mut json = Vec::new();
+        write_json(&[trace], &mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(
+            json.contains(r#"{"samples": 1, "estimate": 4, "std_error": null}"#),
+            "{}",
+            json
+        );
+        assert!(json.contains(r#""method": "circle","#), "{}", json);
+    }
+
+    // Split one CSV row into its fields, undoing RFC 4180 quoting
+    fn read_row(row: &str) -> Vec<String> {
+        let mut fields = vec![String::new()];
+        let mut quoted = false;
+        let mut chars = row.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    fields.last_mut().unwrap().push('"');
+                }
+                '"' => quoted = !quoted,
+                ',' if !quoted => fields.push(String::new()),
+                c => fields.last_mut().unwrap().push(c),
+            }
+        }
+        fields
+    }
+
+    #[test]
+    fn csv_rows_read_back() {
+        let method = "buffons needle (pi-free, \"sobol\")";
+        let trace = Trace {
+            method: method.to_string(),
+            points: vec![TracePoint {
+                samples: 10,
+                estimate: 3.0,
+                std_error: 0.5,
+            }],
+        };
+        let mut csv = Vec::new();
+        write_csv(&[trace], &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let rows: Vec<Vec<String>> = csv.lines().map(read_row).collect();
+        assert_eq!(rows[0], ["method", "samples", "estimate", "std_error"]);
+        assert_eq!(rows[1], [method, "10", "3", "0.5"]);
+        assert_eq!(rows.len(), 2);
+    }
+}